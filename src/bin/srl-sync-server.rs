@@ -0,0 +1,227 @@
+//! Reference sync server for srl.
+//!
+//! Keeps each collection in `<dir>/<name>.json` and serves the protocol
+//! described in `src/sync/protocol.rs` over plain HTTP. Requests are handled
+//! one at a time, so pushes never interleave. Put it behind a reverse proxy
+//! that terminates TLS to sync over the internet.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+#[path = "../sync/protocol.rs"]
+mod protocol;
+
+use protocol::{
+    valid_collection_name, Change, ChangesResponse, PushRequest, PushResponse, PROTOCOL_VERSION,
+    VERSION_HEADER,
+};
+
+/// Largest push accepted, in bytes.
+const MAX_BODY: u64 = 64 * 1024 * 1024;
+
+#[derive(Parser, Debug)]
+#[command(name = "srl-sync-server", about = "Sync server for srl collections")]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8750")]
+    listen: String,
+
+    /// Directory to store collections in (default: <data dir>/srl-sync-server)
+    #[arg(long)]
+    dir: Option<PathBuf>,
+
+    /// Require clients to send this token
+    #[arg(long)]
+    token: Option<String>,
+}
+
+/// A collection as stored on disk: the latest version of every entity ever
+/// pushed, deleted ones included.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Collection {
+    usn: u64,
+    entities: BTreeMap<String, Change>,
+}
+
+impl Collection {
+    fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse {:?}", path))
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string(self)?).with_context(|| format!("Failed to write {:?}", tmp))?;
+        fs::rename(&tmp, path).with_context(|| format!("Failed to write {:?}", path))?;
+        Ok(())
+    }
+
+    /// Entities changed after `since`, oldest first.
+    fn changes_since(&self, since: u64) -> Vec<Change> {
+        let mut changes: Vec<Change> = self.entities.values().filter(|c| c.usn > since).cloned().collect();
+        changes.sort_by_key(|c| c.usn);
+        changes
+    }
+
+    /// Store a push under the next USN.
+    fn apply(&mut self, changes: Vec<Change>) -> u64 {
+        self.usn += 1;
+        for mut change in changes {
+            change.usn = self.usn;
+            self.entities.insert(change.key(), change);
+        }
+        self.usn
+    }
+}
+
+/// A response status and body.
+type Reply = (u16, String);
+
+fn error(status: u16, message: impl Into<String>) -> Reply {
+    (status, message.into())
+}
+
+fn json<T: Serialize>(value: &T) -> Reply {
+    match serde_json::to_string(value) {
+        Ok(body) => (200, body),
+        Err(e) => error(500, e.to_string()),
+    }
+}
+
+fn header(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+struct SyncServer {
+    dir: PathBuf,
+    token: Option<String>,
+}
+
+impl SyncServer {
+    fn handle(&self, request: &mut Request) -> Reply {
+        if let Some(ref token) = self.token {
+            if header(request, "Authorization").as_deref() != Some(format!("Bearer {}", token).as_str()) {
+                return error(401, "missing or wrong token");
+            }
+        }
+        if let Some(version) = header(request, VERSION_HEADER) {
+            if version != PROTOCOL_VERSION.to_string() {
+                return error(
+                    400,
+                    format!("protocol version {} is not supported (server speaks {})", version, PROTOCOL_VERSION),
+                );
+            }
+        }
+
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let Some(name) = path
+            .strip_prefix("/v1/collections/")
+            .and_then(|rest| rest.strip_suffix("/changes"))
+        else {
+            return error(404, "not found");
+        };
+        if !valid_collection_name(name) {
+            return error(400, "invalid collection name");
+        }
+        let path = self.dir.join(format!("{}.json", name));
+
+        let result = match request.method() {
+            Method::Get => self.pull(&path, query),
+            Method::Post => self.push(&path, request),
+            _ => return error(405, "method not allowed"),
+        };
+        result.unwrap_or_else(|e| error(500, format!("{:#}", e)))
+    }
+
+    fn pull(&self, path: &Path, query: &str) -> Result<Reply> {
+        let since = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("since="))
+            .map(str::parse::<u64>)
+            .transpose();
+        let Ok(since) = since else {
+            return Ok(error(400, "invalid `since`"));
+        };
+
+        let collection = Collection::load(path)?;
+        Ok(json(&ChangesResponse {
+            usn: collection.usn,
+            changes: collection.changes_since(since.unwrap_or(0)),
+        }))
+    }
+
+    fn push(&self, path: &Path, request: &mut Request) -> Result<Reply> {
+        let mut body = String::new();
+        request
+            .as_reader()
+            .take(MAX_BODY)
+            .read_to_string(&mut body)
+            .context("Failed to read request body")?;
+        let push: PushRequest = match serde_json::from_str(&body) {
+            Ok(push) => push,
+            Err(e) => return Ok(error(400, format!("invalid push: {}", e))),
+        };
+
+        // Clients name files after these IDs, so one bad push mustn't reach them
+        if push.changes.iter().any(|c| !c.is_well_formed()) {
+            return Ok(error(400, "invalid push: change with a missing or invalid ID"));
+        }
+
+        let mut collection = Collection::load(path)?;
+        if push.base_usn != collection.usn {
+            return Ok(error(
+                409,
+                format!("collection is at usn {}, push was based on {}", collection.usn, push.base_usn),
+            ));
+        }
+        let usn = collection.apply(push.changes);
+        collection.save(path)?;
+        Ok(json(&PushResponse { usn }))
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let dir = args.dir.unwrap_or_else(|| {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("srl-sync-server")
+    });
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+
+    let server = Server::http(&args.listen)
+        .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", args.listen, e))?;
+    let sync = SyncServer { dir, token: args.token };
+    eprintln!("srl-sync-server listening on http://{} (collections in {:?})", args.listen, sync.dir);
+
+    for mut request in server.incoming_requests() {
+        let (status, body) = sync.handle(&mut request);
+        eprintln!("{} {} -> {}", request.method(), request.url(), status);
+
+        let content_type = if status == 200 { "application/json" } else { "text/plain; charset=utf-8" };
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", content_type).expect("valid header"))
+            .with_header(
+                Header::from_bytes(VERSION_HEADER, PROTOCOL_VERSION.to_string()).expect("valid header"),
+            );
+        if let Err(e) = request.respond(response) {
+            eprintln!("Failed to send response: {}", e);
+        }
+    }
+    Ok(())
+}
@@ -11,6 +11,88 @@ pub struct Config {
     /// The currently selected theme name.
     #[serde(default = "default_theme")]
     pub theme: String,
+
+    /// Where decks are stored.
+    #[serde(default)]
+    pub storage_backend: StorageBackend,
+
+    /// Automatic backups.
+    #[serde(default)]
+    pub backup: BackupConfig,
+
+    /// Sync server.
+    #[serde(default)]
+    pub sync: SyncConfig,
+}
+
+/// Automatic backup settings, the `[backup]` table in `config.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Back up all decks when the app exits.
+    pub on_exit: bool,
+    /// Back up at startup when the newest automatic backup is over a day old.
+    pub daily: bool,
+    /// Where automatic backups go. Defaults to a `backups` directory next to
+    /// the default decks directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+    /// Keep the newest backup from each of this many most recent days.
+    pub keep_daily: usize,
+    /// Keep the newest backup from each of this many most recent weeks.
+    pub keep_weekly: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            on_exit: true,
+            daily: true,
+            dir: None,
+            keep_daily: 7,
+            keep_weekly: 4,
+        }
+    }
+}
+
+/// Sync server settings, the `[sync]` table in `config.toml`.
+/// Without a server, syncing goes through git.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// Base URL of an `srl-sync-server`, e.g. `http://127.0.0.1:8750`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Collection on the server to sync with.
+    pub collection: String,
+    /// Token the server was started with, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            server: None,
+            collection: "default".to_string(),
+            token: None,
+        }
+    }
+}
+
+/// Storage backend for decks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// One JSON file per deck
+    #[default]
+    Json,
+    /// One directory per deck with a JSON file per card
+    Directory,
+    /// A single SQLite database with per-card rows
+    Sqlite,
+    /// Kept in memory only; nothing is written to disk
+    Memory,
 }
 
 fn default_theme() -> String {
@@ -21,6 +103,9 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             theme: default_theme(),
+            storage_backend: StorageBackend::default(),
+            backup: BackupConfig::default(),
+            sync: SyncConfig::default(),
         }
     }
 }
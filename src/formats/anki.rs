@@ -0,0 +1,1020 @@
+//! Anki formats: `.apkg` packages and tab/semicolon-separated text exports.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, TimeZone};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::{DeckExporter, DeckImporter, Imported};
+use crate::markup;
+use crate::models::{Card, Deck, Review, ReviewRating};
+use crate::storage::{is_valid_media_name, DeckStorage};
+
+/// Imports Anki packages (`.apkg`) and text exports (`.txt`, `.tsv`).
+pub struct AnkiImporter;
+
+impl AnkiImporter {
+    /// Import cards from an Anki text export (tab-separated or semicolon-separated).
+    /// Format: front<TAB>back or front;back, with optional tags column.
+    pub fn import_anki_text(&self, path: &Path, deck_name: &str) -> Result<Deck> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read Anki text file: {:?}", path))?;
+
+        let mut deck = Deck::new(deck_name.to_string());
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // Detect delimiter: tab or semicolon
+            let parts: Vec<&str> = if line.contains('\t') {
+                line.split('\t').collect()
+            } else {
+                line.split(';').collect()
+            };
+
+            if parts.len() >= 2 {
+                // Exported with "Include HTML" checked, fields are HTML
+                let front = markup::from_html(parts[0].trim());
+                let back = markup::from_html(parts[1].trim());
+
+                if !front.is_empty() && !back.is_empty() {
+                    let mut card = Card::new(front, back);
+
+                    // If there's a third column, treat it as tags
+                    if parts.len() >= 3 {
+                        let tags: Vec<String> = parts[2]
+                            .split_whitespace()
+                            .map(|t| t.to_string())
+                            .collect();
+                        card.tags = tags;
+                    }
+
+                    deck.cards.push(card);
+                }
+            }
+        }
+
+        Ok(deck)
+    }
+
+    /// Import decks from an Anki .apkg package file, or a whole collection
+    /// from a .colpkg backup; they share a layout. Both are ZIP archives
+    /// containing a SQLite database, and media files stored under numbers,
+    /// with a `media` map giving their names. See `PackageVersion` for the
+    /// variants.
+    pub fn import_apkg(&self, path: &Path) -> Result<AnkiPackage> {
+        use rusqlite::Connection;
+        use zip::ZipArchive;
+
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open APKG file: {:?}", path))?;
+
+        let mut archive = ZipArchive::new(file)
+            .with_context(|| "Failed to read APKG as ZIP archive")?;
+
+        let version = PackageVersion::detect(&mut archive)?;
+        let media = read_media_map(&mut archive, version)?;
+
+        // Extract database to a temporary file
+        let db_name = version.collection_file();
+        let mut db_file = archive.by_name(db_name)
+            .with_context(|| format!("Failed to extract {} from APKG", db_name))?;
+
+        // Removed when dropped, however the import ends
+        let mut temp_file = tempfile::Builder::new()
+            .prefix("anki_import_")
+            .suffix(".db")
+            .tempfile()
+            .with_context(|| "Failed to create temporary database file")?;
+        if version.is_compressed() {
+            zstd::stream::copy_decode(&mut db_file, temp_file.as_file_mut())
+        } else {
+            std::io::copy(&mut db_file, temp_file.as_file_mut()).map(|_| ())
+        }
+        .with_context(|| "Failed to extract database")?;
+        let temp_db_path = temp_file.path();
+
+        // Open the SQLite database
+        let conn = Connection::open(temp_db_path)
+            .with_context(|| "Failed to open Anki database")?;
+
+        // Collections from Anki 2.1.28 on keep decks and settings in tables
+        // of their own rather than as JSON in the col table
+        let has_table = |name: &str| {
+            conn.query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?",
+                [name],
+                |row| row.get::<_, i64>(0),
+            )
+            .is_ok_and(|count| count > 0)
+        };
+
+        let deck_names: HashMap<i64, String> = if has_table("decks") {
+            // Nested deck names are separated by 0x1f instead of "::"
+            let mut stmt = conn.prepare("SELECT id, name FROM decks")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?.replace('\x1f', "::")))
+            })?;
+            rows.collect::<rusqlite::Result<_>>()?
+        } else {
+            let mut stmt = conn.prepare("SELECT decks FROM col")?;
+            let decks_json: String = stmt.query_row([], |row| row.get(0))?;
+            let decks: serde_json::Value = serde_json::from_str(&decks_json)?;
+
+            decks
+                .as_object()
+                .map(|obj| {
+                    obj.iter()
+                        .filter_map(|(id, info)| {
+                            let deck_id: i64 = id.parse().ok()?;
+                            let name = info.get("name")?.as_str()?.to_string();
+                            Some((deck_id, name))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        // Review cards' due days count from the collection's creation
+        let crt: i64 = conn.query_row("SELECT crt FROM col", [], |row| row.get(0))?;
+
+        // The v1 scheduler gave (re)learning cards three answer buttons
+        let sched_ver = if has_table("config") {
+            conn.query_row("SELECT val FROM config WHERE KEY = 'schedVer'", [], |row| row.get::<_, Vec<u8>>(0))
+                .ok()
+                .and_then(|val| serde_json::from_slice::<i64>(&val).ok())
+        } else {
+            conn.query_row("SELECT conf FROM col", [], |row| row.get::<_, String>(0))
+                .ok()
+                .and_then(|conf| serde_json::from_str::<serde_json::Value>(&conf).ok())
+                .and_then(|conf| conf.get("schedVer").and_then(|v| v.as_i64()))
+        }
+        .unwrap_or(1);
+
+        // Answers per card, oldest first; manual reschedules have ease 0
+        let mut revlog: HashMap<i64, Vec<Review>> = HashMap::new();
+        if let Ok(mut stmt) = conn.prepare("SELECT id, cid, ease, ivl, factor, type FROM revlog WHERE ease > 0 ORDER BY id") {
+            let rows = stmt.query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(1)?,
+                    AnkiReview {
+                        id: row.get(0)?,
+                        ease: row.get(2)?,
+                        ivl: row.get(3)?,
+                        factor: row.get(4)?,
+                        review_type: row.get(5)?,
+                    },
+                ))
+            })?;
+            for row in rows {
+                let (cid, entry) = row?;
+                let reviews = revlog.entry(cid).or_default();
+                let ease = reviews.last().map_or(2.5, |r: &Review| r.ease_factor);
+                if let Some(review) = entry.to_review(sched_ver, ease) {
+                    reviews.push(review);
+                }
+            }
+        }
+
+        // Query notes and cards with scheduling info
+        // Join notes (for content) with cards (for scheduling and deck assignment).
+        // Cards moved into a filtered deck go back to their original deck
+        // (odid), which also keeps their own due date (odue)
+        let mut stmt = conn.prepare(
+            "SELECT n.flds,
+                    CASE WHEN c.odid != 0 THEN c.odid ELSE c.did END,
+                    c.id, c.type, c.queue,
+                    CASE WHEN c.odid != 0 THEN c.odue ELSE c.due END,
+                    c.ivl, c.factor, c.reps, c.lapses
+             FROM notes n
+             JOIN cards c ON c.nid = n.id"
+        )?;
+
+        // Group cards by deck
+        let mut decks_map: HashMap<i64, Vec<Card>> = HashMap::new();
+
+        let rows = stmt.query_map([], |row| {
+            let flds: String = row.get(0)?;
+            let did: i64 = row.get(1)?;
+            let cid: i64 = row.get(2)?;
+            let scheduling = AnkiScheduling {
+                card_type: row.get(3)?,
+                queue: row.get(4)?,
+                due: row.get(5)?,
+                ivl: row.get(6)?,
+                factor: row.get(7)?,
+                reps: row.get(8)?,
+                lapses: row.get(9)?,
+            };
+            Ok((flds, did, cid, scheduling))
+        })?;
+
+        for row in rows {
+            let (flds, did, cid, scheduling) = row?;
+
+            // Split fields by Anki's field separator (0x1f)
+            let fields: Vec<&str> = flds.split('\x1f').collect();
+            if fields.len() < 2 {
+                continue;
+            }
+
+            let front = markup::from_html(fields[0]);
+            let back = markup::from_html(fields[1]);
+
+            if front.is_empty() || back.is_empty() {
+                continue;
+            }
+
+            // Create card with imported scheduling data
+            let mut card = Card::new(front, back);
+            scheduling.apply(&mut card, crt, revlog.remove(&cid).unwrap_or_default());
+
+            decks_map.entry(did).or_default().push(card);
+        }
+
+        // Create Deck objects
+        let mut result = Vec::new();
+        for (did, cards) in decks_map {
+            let name = deck_names
+                .get(&did)
+                .cloned()
+                .unwrap_or_else(|| format!("Imported Deck {}", did));
+
+            let mut deck = Deck::new(name);
+            deck.cards = cards;
+            result.push(deck);
+        }
+
+        if result.is_empty() {
+            anyhow::bail!("No cards found in APKG file");
+        }
+
+        Ok(AnkiPackage {
+            decks: result,
+            media: AnkiMedia {
+                archive: Some(path.to_path_buf()),
+                entries: media,
+                compressed: version.is_compressed(),
+            },
+        })
+    }
+
+    /// Import an Anki package or text export, detecting the format from the
+    /// file. Text exports have no media.
+    pub fn import_package(&self, path: &Path, deck_name: Option<&str>) -> Result<AnkiPackage> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+
+        let deck = match extension.as_deref() {
+            Some("apkg") | Some("colpkg") => return self.import_apkg(path),
+            Some("txt") | Some("tsv") => {
+                let name = deck_name.unwrap_or_else(|| {
+                    path.file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("Imported Deck")
+                });
+                self.import_anki_text(path, name)?
+            }
+            _ => {
+                // Try to detect format from content; packages are ZIP archives
+                let mut magic = [0u8; 4];
+                let is_zip = File::open(path).and_then(|mut f| f.read_exact(&mut magic)).is_ok()
+                    && magic == *b"PK\x03\x04";
+                if is_zip {
+                    return self.import_apkg(path);
+                }
+                let content = fs::read_to_string(path)?;
+                if content.contains('\t') || content.contains(';') {
+                    let name = deck_name.unwrap_or("Imported Deck");
+                    self.import_anki_text(path, name)?
+                } else {
+                    anyhow::bail!(
+                        "Unknown file format. Expected .apkg, .colpkg, .txt, or .tsv file."
+                    )
+                }
+            }
+        };
+        Ok(AnkiPackage {
+            decks: vec![deck],
+            media: AnkiMedia::default(),
+        })
+    }
+}
+
+/// Decks imported from an Anki package, with the package's media.
+pub struct AnkiPackage {
+    pub decks: Vec<Deck>,
+    pub media: AnkiMedia,
+}
+
+/// Media files of an Anki package, read from the archive as they are saved.
+#[derive(Default)]
+pub struct AnkiMedia {
+    archive: Option<PathBuf>,
+    /// Archive entry of each media file, by name.
+    entries: HashMap<String, String>,
+    /// Whether the entries are zstd-compressed.
+    compressed: bool,
+}
+
+impl AnkiMedia {
+    /// Copy the media files a deck's cards refer to into its media folder.
+    /// Referenced files missing from the package are left out.
+    /// Returns the number of files copied.
+    pub fn save_for(&self, deck: &Deck, storage: &DeckStorage) -> Result<usize> {
+        let Some(ref archive) = self.archive else {
+            return Ok(0);
+        };
+        let names: BTreeSet<&str> = deck.cards.iter().flat_map(|c| c.media_names()).collect();
+        let wanted: Vec<(&str, &String)> = names
+            .into_iter()
+            .filter_map(|name| self.entries.get(name).map(|entry| (name, entry)))
+            .collect();
+        if wanted.is_empty() {
+            return Ok(0);
+        }
+
+        let file = File::open(archive).with_context(|| format!("Failed to open APKG file: {:?}", archive))?;
+        let mut zip = zip::ZipArchive::new(file).context("Failed to read APKG as ZIP archive")?;
+        for (name, entry) in &wanted {
+            let mut data = Vec::new();
+            let mut file = zip
+                .by_name(entry)
+                .with_context(|| format!("Failed to extract media file {} from APKG", name))?;
+            if self.compressed {
+                zstd::stream::copy_decode(&mut file, &mut data)
+            } else {
+                file.read_to_end(&mut data).map(|_| ())
+            }
+            .with_context(|| format!("Failed to extract media file {} from APKG", name))?;
+            storage.save_media(&deck.id, name, &data)?;
+        }
+        Ok(wanted.len())
+    }
+}
+
+/// The variants of `.apkg` and `.colpkg` archives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PackageVersion {
+    /// `collection.anki2`, from Anki 2.0.
+    Legacy1,
+    /// `collection.anki21`, from Anki 2.1.
+    Legacy2,
+    /// zstd-compressed `collection.anki21b`, from Anki 2.1.50 on, with a
+    /// protobuf `meta` entry saying so. Its media map is protobuf too, and
+    /// media files are compressed as well. These archives also carry a
+    /// `collection.anki2` asking older versions to update.
+    Latest,
+}
+
+impl PackageVersion {
+    fn detect<R: Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>) -> Result<Self> {
+        if let Ok(mut meta) = archive.by_name("meta") {
+            let mut buf = Vec::new();
+            meta.read_to_end(&mut buf).context("Failed to read the APKG meta file")?;
+            // message PackageMetadata { Version version = 1; }
+            let version = proto_fields(&buf)?
+                .into_iter()
+                .find_map(|(field, value)| match (field, value) {
+                    (1, ProtoValue::Varint(version)) => Some(version),
+                    _ => None,
+                })
+                .unwrap_or(0);
+            return match version {
+                1 => Ok(Self::Legacy1),
+                2 => Ok(Self::Legacy2),
+                3 => Ok(Self::Latest),
+                _ => anyhow::bail!(
+                    "This package was made by a newer version of Anki; export it again \
+                     with \"Support older Anki versions\" checked"
+                ),
+            };
+        }
+
+        if archive.file_names().any(|n| n == "collection.anki21") {
+            Ok(Self::Legacy2)
+        } else if archive.file_names().any(|n| n == "collection.anki2") {
+            Ok(Self::Legacy1)
+        } else {
+            anyhow::bail!(
+                "No Anki database found in APKG file \
+                 (expected collection.anki21b, collection.anki21 or collection.anki2)"
+            );
+        }
+    }
+
+    fn collection_file(self) -> &'static str {
+        match self {
+            Self::Legacy1 => "collection.anki2",
+            Self::Legacy2 => "collection.anki21",
+            Self::Latest => "collection.anki21b",
+        }
+    }
+
+    fn is_compressed(self) -> bool {
+        self == Self::Latest
+    }
+}
+
+/// Archive entry names of the package's media files, by file name.
+/// Packages without media may leave the map out.
+fn read_media_map<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    version: PackageVersion,
+) -> Result<HashMap<String, String>> {
+    let Ok(mut entry) = archive.by_name("media") else {
+        return Ok(HashMap::new());
+    };
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data).context("Failed to read the APKG media list")?;
+
+    let numbered: Vec<(String, String)> = if version.is_compressed() {
+        // message MediaEntries { repeated MediaEntry entries = 1; }
+        // message MediaEntry { string name = 1; ...; optional uint32 legacy_zip_filename = 255; }
+        // Files are stored under their index unless legacy_zip_filename says otherwise
+        let data = zstd::stream::decode_all(data.as_slice()).context("Failed to read the APKG media list")?;
+        let mut numbered = Vec::new();
+        for (index, (_, value)) in proto_fields(&data)?.into_iter().filter(|(field, _)| *field == 1).enumerate() {
+            let ProtoValue::Bytes(entry) = value else {
+                continue;
+            };
+            let mut name = None;
+            let mut zip_name = index.to_string();
+            for (field, value) in proto_fields(entry)? {
+                match (field, value) {
+                    (1, ProtoValue::Bytes(bytes)) => name = Some(String::from_utf8_lossy(bytes).into_owned()),
+                    (255, ProtoValue::Varint(number)) => zip_name = number.to_string(),
+                    _ => {}
+                }
+            }
+            numbered.extend(name.map(|name| (zip_name, name)));
+        }
+        numbered
+    } else {
+        let map: HashMap<String, String> =
+            serde_json::from_slice(&data).context("Failed to parse the APKG media list")?;
+        map.into_iter().collect()
+    };
+
+    Ok(numbered
+        .into_iter()
+        .filter(|(_, name)| is_valid_media_name(name))
+        .map(|(entry, name)| (name, entry))
+        .collect())
+}
+
+/// A protobuf field value, as far as Anki's package files need.
+enum ProtoValue<'a> {
+    Varint(u64),
+    /// Length-delimited: strings, bytes and nested messages.
+    Bytes(&'a [u8]),
+    /// Fixed-width numbers, which srl doesn't read.
+    Fixed,
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos).context("Truncated protobuf message")?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    anyhow::bail!("Invalid protobuf varint")
+}
+
+/// Split a protobuf message into its fields, in order.
+fn proto_fields(data: &[u8]) -> Result<Vec<(u64, ProtoValue<'_>)>> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let key = read_varint(data, &mut pos)?;
+        let value = match key & 7 {
+            0 => ProtoValue::Varint(read_varint(data, &mut pos)?),
+            1 | 5 => {
+                pos += if key & 7 == 1 { 8 } else { 4 };
+                ProtoValue::Fixed
+            }
+            2 => {
+                let len = read_varint(data, &mut pos)? as usize;
+                let bytes = data
+                    .get(pos..pos.saturating_add(len))
+                    .context("Truncated protobuf message")?;
+                pos += len;
+                ProtoValue::Bytes(bytes)
+            }
+            wire_type => anyhow::bail!("Unsupported protobuf wire type {}", wire_type),
+        };
+        fields.push((key >> 3, value));
+    }
+    Ok(fields)
+}
+
+/// Scheduling columns of a row of Anki's `cards` table.
+struct AnkiScheduling {
+    /// 0 new, 1 learning, 2 review, 3 relearning.
+    card_type: i64,
+    /// As `card_type`, or -1 suspended, -2/-3 buried.
+    queue: i64,
+    /// Position for new cards, a timestamp for (re)learning cards, and days
+    /// since the collection was created for review cards.
+    due: i64,
+    ivl: i64,
+    /// Ease in permille; 0 for cards never answered.
+    factor: i64,
+    reps: i64,
+    lapses: i64,
+}
+
+impl AnkiScheduling {
+    /// Copy the scheduling state onto `card`. `crt` is when the collection
+    /// was created, and `reviews` the card's answers from the revlog.
+    fn apply(&self, card: &mut Card, crt: i64, reviews: Vec<Review>) {
+        if self.factor > 0 {
+            card.ease_factor = (self.factor as f64 / 1000.0).max(1.3);
+        }
+        card.lapses = self.lapses.max(0) as u32;
+        // Packages exported without scheduling information have no revlog
+        card.total_reviews = if reviews.is_empty() { self.reps.max(0) as u32 } else { reviews.len() as u32 };
+        card.last_reviewed = reviews.last().map(|r| r.reviewed_at);
+        card.reviews = reviews;
+
+        match self.card_type {
+            // Review cards count as graduated, so the next interval builds on Anki's
+            2 => {
+                card.interval = self.ivl.max(1) as u32;
+                card.repetitions = self.reps.max(2) as u32;
+                card.due_date = anki_due_date(self.due, crt);
+            }
+            // (Re)learning cards start over from the first step, as after a lapse here
+            1 | 3 => {
+                card.interval = self.ivl.max(0) as u32;
+                card.repetitions = 0;
+                card.due_date = anki_due_date(self.due, crt);
+            }
+            _ => {}
+        }
+
+        match self.queue {
+            -1 => card.suspended = true,
+            // Anki brings buried cards back the next day
+            -2 | -3 => {
+                let tomorrow = Local::now().date_naive() + chrono::Duration::days(1);
+                card.buried_until = tomorrow
+                    .and_hms_opt(0, 0, 0)
+                    .and_then(|midnight| Local.from_local_datetime(&midnight).earliest());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A row of Anki's `revlog` table.
+struct AnkiReview {
+    /// When the card was answered, in milliseconds.
+    id: i64,
+    /// Button pressed, 1 (Again) to 4 (Easy).
+    ease: i64,
+    /// Interval scheduled: days if positive, seconds if negative.
+    ivl: i64,
+    /// Ease in permille; 0 while a new card is being learned.
+    factor: i64,
+    /// 0 learning, 1 review, 2 relearning, 3 filtered deck.
+    review_type: i64,
+}
+
+impl AnkiReview {
+    /// The review as srl logs it. `sched_ver` is the collection's scheduler
+    /// version, and `ease` the card's ease factor before this review.
+    fn to_review(&self, sched_ver: i64, ease: f64) -> Option<Review> {
+        let learning = matches!(self.review_type, 0 | 2);
+        let rating = match (self.ease, sched_ver < 2 && learning) {
+            (1, _) => ReviewRating::Again,
+            // Learning cards under the v1 scheduler had Again, Good and Easy
+            (2, true) => ReviewRating::Good,
+            (3, true) => ReviewRating::Easy,
+            (2, false) => ReviewRating::Hard,
+            (3, false) => ReviewRating::Good,
+            (4, false) => ReviewRating::Easy,
+            _ => return None,
+        };
+        Some(Review {
+            reviewed_at: Local.timestamp_millis_opt(self.id).single()?,
+            rating,
+            interval: self.ivl.max(0) as u32,
+            ease_factor: if self.factor > 0 { (self.factor as f64 / 1000.0).max(1.3) } else { ease },
+        })
+    }
+}
+
+/// When a card is due. Anki stores this as a Unix timestamp for learning
+/// cards and as days since the collection's creation time `crt` for review
+/// cards; suspended and buried cards keep whichever they had, so tell them
+/// apart by size.
+fn anki_due_date(due: i64, crt: i64) -> Option<DateTime<Local>> {
+    let secs = if due >= 1_000_000_000 { due } else { crt + due * 86_400 };
+    Local.timestamp_opt(secs, 0).single()
+}
+
+impl DeckImporter for AnkiImporter {
+    fn name(&self) -> &'static str {
+        "Anki"
+    }
+
+    /// Auto-detect Anki format and import.
+    /// Returns the imported decks; use `import_package` to keep their media.
+    fn import(&self, path: &Path, deck_name: Option<&str>) -> Result<Imported> {
+        Ok(Imported {
+            decks: self.import_package(path, deck_name)?.decks,
+            skipped: Vec::new(),
+        })
+    }
+}
+
+/// Exports decks to an Anki `.apkg` package.
+#[derive(Default)]
+pub struct AnkiPackageExporter<'a> {
+    /// Where to read the decks' media files from; without it, packages
+    /// keep the references but not the files.
+    media: Option<&'a DeckStorage>,
+}
+
+impl<'a> AnkiPackageExporter<'a> {
+    /// An exporter that packages the media files the cards refer to.
+    pub fn with_media(storage: &'a DeckStorage) -> Self {
+        Self { media: Some(storage) }
+    }
+}
+
+impl DeckExporter for AnkiPackageExporter<'_> {
+    fn name(&self) -> &'static str {
+        "Anki"
+    }
+
+    /// Export decks to an Anki .apkg package file.
+    /// Preserves scheduling data (interval, ease factor, repetitions, lapses).
+    fn export(&self, decks: &[Deck], path: &Path) -> Result<usize> {
+        use rusqlite::Connection;
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+        use zip::ZipWriter;
+
+        if decks.is_empty() {
+            anyhow::bail!("No decks to export");
+        }
+
+        // Create temporary SQLite database
+        // Removed when dropped, however the export ends
+        let temp_file = tempfile::Builder::new()
+            .prefix("anki_export_")
+            .suffix(".db")
+            .tempfile()
+            .with_context(|| "Failed to create temporary database")?;
+        let temp_db_path = temp_file.path();
+        let conn = Connection::open(temp_db_path)
+            .with_context(|| "Failed to create temporary database")?;
+
+        // Create Anki schema
+        conn.execute_batch(
+            r#"
+            CREATE TABLE col (
+                id INTEGER PRIMARY KEY,
+                crt INTEGER NOT NULL,
+                mod INTEGER NOT NULL,
+                scm INTEGER NOT NULL,
+                ver INTEGER NOT NULL,
+                dty INTEGER NOT NULL,
+                usn INTEGER NOT NULL,
+                ls INTEGER NOT NULL,
+                conf TEXT NOT NULL,
+                models TEXT NOT NULL,
+                decks TEXT NOT NULL,
+                dconf TEXT NOT NULL,
+                tags TEXT NOT NULL
+            );
+            CREATE TABLE notes (
+                id INTEGER PRIMARY KEY,
+                guid TEXT NOT NULL,
+                mid INTEGER NOT NULL,
+                mod INTEGER NOT NULL,
+                usn INTEGER NOT NULL,
+                tags TEXT NOT NULL,
+                flds TEXT NOT NULL,
+                sfld TEXT NOT NULL,
+                csum INTEGER NOT NULL,
+                flags INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE cards (
+                id INTEGER PRIMARY KEY,
+                nid INTEGER NOT NULL,
+                did INTEGER NOT NULL,
+                ord INTEGER NOT NULL,
+                mod INTEGER NOT NULL,
+                usn INTEGER NOT NULL,
+                type INTEGER NOT NULL,
+                queue INTEGER NOT NULL,
+                due INTEGER NOT NULL,
+                ivl INTEGER NOT NULL,
+                factor INTEGER NOT NULL,
+                reps INTEGER NOT NULL,
+                lapses INTEGER NOT NULL,
+                left INTEGER NOT NULL,
+                odue INTEGER NOT NULL,
+                odid INTEGER NOT NULL,
+                flags INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE revlog (
+                id INTEGER PRIMARY KEY,
+                cid INTEGER NOT NULL,
+                usn INTEGER NOT NULL,
+                ease INTEGER NOT NULL,
+                ivl INTEGER NOT NULL,
+                lastIvl INTEGER NOT NULL,
+                factor INTEGER NOT NULL,
+                time INTEGER NOT NULL,
+                type INTEGER NOT NULL
+            );
+            CREATE TABLE graves (
+                usn INTEGER NOT NULL,
+                oid INTEGER NOT NULL,
+                type INTEGER NOT NULL
+            );
+            "#,
+        )?;
+
+        let now = chrono::Utc::now().timestamp();
+        let now_millis = now * 1000;
+
+        // Build deck JSON for col table
+        let mut decks_json = serde_json::Map::new();
+        // Default deck (id=1)
+        decks_json.insert(
+            "1".to_string(),
+            serde_json::json!({
+                "id": 1,
+                "name": "Default",
+                "mod": now,
+                "usn": -1,
+                "lrnToday": [0, 0],
+                "revToday": [0, 0],
+                "newToday": [0, 0],
+                "timeToday": [0, 0],
+                "collapsed": false,
+                "desc": "",
+                "dyn": 0,
+                "conf": 1,
+                "extendNew": 10,
+                "extendRev": 50
+            }),
+        );
+
+        // Add our decks
+        for (i, deck) in decks.iter().enumerate() {
+            let deck_id = (i as i64 + 2) * 1000000000000i64 + 1;
+            decks_json.insert(
+                deck_id.to_string(),
+                serde_json::json!({
+                    "id": deck_id,
+                    "name": deck.name,
+                    "mod": now,
+                    "usn": -1,
+                    "lrnToday": [0, 0],
+                    "revToday": [0, 0],
+                    "newToday": [0, 0],
+                    "timeToday": [0, 0],
+                    "collapsed": false,
+                    "desc": deck.description,
+                    "dyn": 0,
+                    "conf": 1,
+                    "extendNew": 10,
+                    "extendRev": 50
+                }),
+            );
+        }
+
+        // Basic model (note type) for simple front/back cards
+        let model_id: i64 = 1000000000001;
+        let models_json = serde_json::json!({
+            model_id.to_string(): {
+                "id": model_id,
+                "name": "Basic",
+                "type": 0,
+                "mod": now,
+                "usn": -1,
+                "sortf": 0,
+                "did": 1,
+                "tmpls": [{
+                    "name": "Card 1",
+                    "ord": 0,
+                    "qfmt": "{{Front}}",
+                    "afmt": "{{FrontSide}}<hr id=answer>{{Back}}",
+                    "did": null,
+                    "bqfmt": "",
+                    "bafmt": ""
+                }],
+                "flds": [
+                    {"name": "Front", "ord": 0, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": []},
+                    {"name": "Back", "ord": 1, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": []}
+                ],
+                "css": ".card { font-family: arial; font-size: 20px; text-align: center; color: black; background-color: white; }",
+                "latexPre": "",
+                "latexPost": "",
+                "latexsvg": false,
+                "req": [[0, "all", [0]]]
+            }
+        });
+
+        // Default deck config
+        let dconf_json = serde_json::json!({
+            "1": {
+                "id": 1,
+                "name": "Default",
+                "replayq": true,
+                "lapse": {"leechFails": 8, "minInt": 1, "delays": [10], "leechAction": 0, "mult": 0},
+                "rev": {"perDay": 200, "fuzz": 0.05, "ivlFct": 1, "maxIvl": 36500, "ease4": 1.3, "bury": false, "hardFactor": 1.2},
+                "new": {"perDay": 20, "delays": [1, 10], "separate": true, "ints": [1, 4, 7], "initialFactor": 2500, "bury": false, "order": 1},
+                "maxTaken": 60,
+                "timer": 0,
+                "autoplay": true,
+                "mod": 0,
+                "usn": 0
+            }
+        });
+
+        // Insert collection metadata
+        conn.execute(
+            // schedVer 2: the revlog's answers are out of four buttons throughout
+            "INSERT INTO col VALUES (1, ?, ?, ?, 11, 0, -1, 0, '{\"schedVer\": 2}', ?, ?, ?, '{}')",
+            rusqlite::params![
+                now,
+                now,
+                now_millis,
+                models_json.to_string(),
+                serde_json::Value::Object(decks_json).to_string(),
+                dconf_json.to_string(),
+            ],
+        )?;
+
+        // Insert notes and cards
+        let mut note_id: i64 = now_millis;
+        let mut card_id: i64 = now_millis;
+        let mut total_cards = 0;
+        let mut guids = std::collections::HashSet::new();
+        let mut revlog_ids = std::collections::HashSet::new();
+
+        for (deck_idx, deck) in decks.iter().enumerate() {
+            let deck_id = (deck_idx as i64 + 2) * 1000000000000i64 + 1;
+
+            for card in &deck.cards {
+                note_id += 1;
+                card_id += 1;
+
+                // Anki matches notes by guid across the whole collection, but
+                // card IDs are only unique within a deck (and short in older decks)
+                let mut guid = card.id.clone();
+                if !guids.insert(guid.clone()) {
+                    guid = format!("{}{}", deck.id, card.id);
+                    guids.insert(guid.clone());
+                }
+
+                // Fields separated by 0x1f
+                let flds = format!("{}\x1f{}", markup::to_html(&card.front), markup::to_html(&card.back));
+                let tags = card.tags.join(" ");
+
+                // Simple checksum of front field
+                let csum: i64 = card.front.bytes().map(|b| b as i64).sum::<i64>() % 2147483647;
+
+                // Insert note
+                conn.execute(
+                    "INSERT INTO notes VALUES (?, ?, ?, ?, -1, ?, ?, ?, ?, 0, '')",
+                    rusqlite::params![
+                        note_id,
+                        &guid,
+                        model_id,
+                        now,
+                        tags,
+                        flds,
+                        &card.front,  // sfld (sort field)
+                        csum,
+                    ],
+                )?;
+
+                // Determine card type and queue
+                let due_at = card.due_date.map_or(now, |d| d.timestamp());
+                let (card_type, mut queue, due) = if card.repetitions == 0 && card.due_date.is_none() {
+                    (0, 0, note_id)  // New card
+                } else if card.interval == 0 || card.repetitions == 0 {
+                    (1, 1, due_at)  // Learning
+                } else {
+                    // Review card - due is days since collection creation
+                    let due_days = (due_at - now).div_euclid(86_400);
+                    (2, 2, due_days)
+                };
+                if card.suspended {
+                    queue = -1;
+                } else if card.is_held() {
+                    queue = -2;
+                }
+
+                // Insert card with scheduling data
+                conn.execute(
+                    "INSERT INTO cards VALUES (?, ?, ?, 0, ?, -1, ?, ?, ?, ?, ?, ?, ?, 0, 0, 0, 0, '')",
+                    rusqlite::params![
+                        card_id,
+                        note_id,
+                        deck_id,
+                        now,
+                        card_type,
+                        queue,
+                        due,
+                        card.interval as i64,
+                        (card.ease_factor * 1000.0) as i64,
+                        card.repetitions as i64,
+                        card.lapses as i64,
+                    ],
+                )?;
+
+                // Review history; revlog IDs are answer times in milliseconds
+                // and must be unique across the collection
+                let mut last_interval = 0;
+                for review in &card.reviews {
+                    let mut revlog_id = review.reviewed_at.timestamp_millis();
+                    while !revlog_ids.insert(revlog_id) {
+                        revlog_id += 1;
+                    }
+                    let review_type = if last_interval == 0 { 0 } else { 1 };
+                    conn.execute(
+                        "INSERT INTO revlog VALUES (?, ?, -1, ?, ?, ?, ?, 0, ?)",
+                        rusqlite::params![
+                            revlog_id,
+                            card_id,
+                            review.rating as i64 + 1,
+                            review.interval as i64,
+                            last_interval as i64,
+                            (review.ease_factor * 1000.0) as i64,
+                            review_type,
+                        ],
+                    )?;
+                    last_interval = review.interval;
+                }
+
+                total_cards += 1;
+            }
+        }
+
+        conn.close().map_err(|(_, e)| e)?;
+
+        // Create the APKG (ZIP) file
+        let apkg_file = File::create(path)
+            .with_context(|| format!("Failed to create APKG file: {:?}", path))?;
+        let mut zip = ZipWriter::new(apkg_file);
+
+        let options = SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+
+        // Add the database
+        zip.start_file("collection.anki2", options)?;
+        let db_bytes = fs::read(temp_db_path)?;
+        zip.write_all(&db_bytes)?;
+
+        // Media files go in as "0", "1", ..., named by the media map. Anki
+        // keeps all media in one folder, so a name used by several decks is
+        // packaged once
+        let mut media_map = serde_json::Map::new();
+        let mut packaged = std::collections::HashSet::new();
+        if let Some(storage) = self.media {
+            for deck in decks {
+                for name in deck.cards.iter().flat_map(|c| c.media_names()) {
+                    if packaged.contains(name) {
+                        continue;
+                    }
+                    let Some(data) = storage.load_media(&deck.id, name)? else {
+                        continue;
+                    };
+                    let entry = media_map.len().to_string();
+                    zip.start_file(entry.as_str(), options)?;
+                    zip.write_all(&data)?;
+                    media_map.insert(entry, serde_json::Value::String(name.to_string()));
+                    packaged.insert(name);
+                }
+            }
+        }
+        zip.start_file("media", options)?;
+        zip.write_all(serde_json::Value::Object(media_map).to_string().as_bytes())?;
+
+        zip.finish()?;
+
+        Ok(total_cards)
+    }
+}
@@ -0,0 +1,325 @@
+//! Comma-separated files (RFC 4180), one card per row.
+//!
+//! Columns map to card fields by a header row naming them, by an explicit
+//! mapping, or else as `front,back`. Quoted fields may span lines. Rows that
+//! can't be imported are reported with their line number.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use super::{filename_to_title_case, DeckExporter, DeckImporter, Imported};
+use crate::models::{Card, Deck};
+use crate::storage::is_valid_id;
+
+/// Card field held by a CSV column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvColumn {
+    /// Card ID, which update imports match cards by.
+    Id,
+    Front,
+    Back,
+    Notes,
+    /// Space-separated tags.
+    Tags,
+    /// Days between reviews.
+    Interval,
+    /// Ease factor, like `2.5` or `250%`.
+    Ease,
+    /// Next review, as `YYYY-MM-DD` or an RFC 3339 date and time.
+    Due,
+    /// Left out of the import.
+    Ignore,
+}
+
+impl CsvColumn {
+    const NAMES: [(&'static str, CsvColumn); 8] = [
+        ("id", CsvColumn::Id),
+        ("front", CsvColumn::Front),
+        ("back", CsvColumn::Back),
+        ("notes", CsvColumn::Notes),
+        ("tags", CsvColumn::Tags),
+        ("interval", CsvColumn::Interval),
+        ("ease", CsvColumn::Ease),
+        ("due", CsvColumn::Due),
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Self::NAMES.iter().find(|(n, _)| *n == name).map(|(_, column)| *column)
+    }
+
+    /// Parse a column mapping like `front,back,-,tags`: one field name per
+    /// column, with `-` (or nothing) for columns to leave out.
+    pub fn parse_list(spec: &str) -> Result<Vec<Self>> {
+        let columns = spec
+            .split(',')
+            .map(|name| match name.trim() {
+                "" | "-" => Ok(Self::Ignore),
+                name => Self::from_name(name).with_context(|| {
+                    format!(
+                        "Unknown CSV column '{}' (expected id, front, back, notes, tags, interval, ease, due or -)",
+                        name
+                    )
+                }),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for (name, column) in Self::NAMES {
+            let count = columns.iter().filter(|c| **c == column).count();
+            if count > 1 {
+                anyhow::bail!("The CSV column mapping has {} '{}' columns", count, name);
+            }
+        }
+        if !columns.contains(&Self::Front) || !columns.contains(&Self::Back) {
+            anyhow::bail!("The CSV column mapping needs a front and a back column");
+        }
+        Ok(columns)
+    }
+
+    /// The mapping a header row names, if the row is one: it has to name
+    /// the front and back columns. Columns it doesn't know are left out.
+    fn from_header(record: &::csv::StringRecord) -> Option<Vec<Self>> {
+        let columns: Vec<Self> = record
+            .iter()
+            .map(|name| Self::from_name(name).unwrap_or(Self::Ignore))
+            .collect();
+        (columns.contains(&Self::Front) && columns.contains(&Self::Back)).then_some(columns)
+    }
+}
+
+/// Parse a field delimiter: a single character, or `tab`.
+pub fn parse_delimiter(value: &str) -> Result<u8> {
+    match value {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => anyhow::bail!("The CSV delimiter must be a single ASCII character or 'tab', not '{}'", value),
+    }
+}
+
+/// Imports decks from CSV files, one card per row.
+#[derive(Debug, Clone)]
+pub struct CsvImporter {
+    delimiter: u8,
+    columns: Option<Vec<CsvColumn>>,
+}
+
+impl Default for CsvImporter {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            columns: None,
+        }
+    }
+}
+
+impl CsvImporter {
+    /// Separate fields with `delimiter` instead of a comma.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Map the columns to these fields in order, rather than by the header.
+    pub fn with_columns(mut self, columns: Vec<CsvColumn>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    /// Import cards from a CSV file. Rows that can't be imported are
+    /// skipped and reported by line.
+    pub fn import_csv(&self, csv_path: &Path, deck_name: &str) -> Result<Imported> {
+        let content = fs::read(csv_path).with_context(|| format!("Failed to read {:?}", csv_path))?;
+        let mut reader = ::csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(content.as_slice());
+
+        let mut deck = Deck::new(deck_name.to_string());
+        let mut skipped = Vec::new();
+        let mut columns = self.columns.clone();
+        let mut ids = HashSet::new();
+
+        for (i, record) in reader.records().enumerate() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    let line = e.position().map_or(0, |p| p.line());
+                    skipped.push(format!("line {}: {}", line, e));
+                    continue;
+                }
+            };
+            let line = record.position().map_or(i as u64 + 1, |p| p.line());
+
+            if i == 0 {
+                if let Some(header) = CsvColumn::from_header(&record) {
+                    columns.get_or_insert(header);
+                    continue;
+                }
+            }
+
+            let columns = columns.get_or_insert_with(|| vec![CsvColumn::Front, CsvColumn::Back]);
+            match card_from_row(&record, columns) {
+                Ok(card) if !ids.insert(card.id.clone()) => {
+                    skipped.push(format!("line {}: ID '{}' is used by an earlier row", line, card.id));
+                }
+                Ok(card) => deck.cards.push(card),
+                Err(e) => skipped.push(format!("line {}: {:#}", line, e)),
+            }
+        }
+
+        Ok(Imported {
+            decks: vec![deck],
+            skipped,
+        })
+    }
+}
+
+impl DeckImporter for CsvImporter {
+    fn name(&self) -> &'static str {
+        "CSV"
+    }
+
+    fn import(&self, path: &Path, deck_name: Option<&str>) -> Result<Imported> {
+        let name = match deck_name {
+            Some(name) => name.to_string(),
+            None => path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(filename_to_title_case)
+                .unwrap_or_else(|| "Imported Deck".to_string()),
+        };
+        self.import_csv(path, &name)
+    }
+}
+
+/// Build a card from a row, its fields mapped by `columns`.
+fn card_from_row(record: &::csv::StringRecord, columns: &[CsvColumn]) -> Result<Card> {
+    let mut card = Card::new(String::new(), String::new());
+    let mut interval = None;
+    let mut due = None;
+
+    for (column, value) in columns.iter().zip(record.iter()) {
+        let value = value.trim();
+        match column {
+            CsvColumn::Id if !value.is_empty() => {
+                if !is_valid_id(value) {
+                    anyhow::bail!("'{}' is not a valid card ID", value);
+                }
+                card.id = value.to_string();
+            }
+            CsvColumn::Front => card.front = value.to_string(),
+            CsvColumn::Back => card.back = value.to_string(),
+            CsvColumn::Notes => card.notes = value.to_string(),
+            CsvColumn::Tags => card.tags = value.split_whitespace().map(String::from).collect(),
+            _ if value.is_empty() => {}
+            CsvColumn::Interval => {
+                let days: u32 = value
+                    .parse()
+                    .ok()
+                    .with_context(|| format!("interval '{}' is not a whole number of days", value))?;
+                interval = Some(days);
+            }
+            CsvColumn::Ease => card.ease_factor = parse_ease(value)?,
+            CsvColumn::Due => due = Some(parse_due(value)?),
+            CsvColumn::Id | CsvColumn::Ignore => {}
+        }
+    }
+
+    if card.front.is_empty() {
+        anyhow::bail!("the front is empty");
+    }
+    if card.back.is_empty() {
+        anyhow::bail!("the back is empty");
+    }
+
+    match interval {
+        // Cards with an interval have graduated, so later intervals build on it
+        Some(days) if days > 0 => {
+            card.interval = days;
+            card.repetitions = 2;
+            card.due_date = Some(due.unwrap_or_else(|| Local::now() + Duration::days(days.into())));
+        }
+        _ => card.due_date = due,
+    }
+    Ok(card)
+}
+
+/// Parse an ease factor, either as is (`2.5`) or as Anki shows it (`250%`).
+fn parse_ease(value: &str) -> Result<f64> {
+    let (number, scale) = match value.strip_suffix('%') {
+        Some(percent) => (percent.trim(), 100.0),
+        None => (value, 1.0),
+    };
+    let ease = number
+        .parse::<f64>()
+        .ok()
+        .map(|n| n / scale)
+        .filter(|ease| (1.3..=10.0).contains(ease))
+        .with_context(|| format!("ease '{}' is not an ease factor from 1.3 (or 130%) up", value))?;
+    Ok(ease)
+}
+
+/// Parse a due date, `YYYY-MM-DD` (the start of that day) or RFC 3339.
+pub(super) fn parse_due(value: &str) -> Result<DateTime<Local>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let start = date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|midnight| Local.from_local_datetime(&midnight).earliest());
+        if let Some(start) = start {
+            return Ok(start);
+        }
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|due| due.with_timezone(&Local))
+        .ok()
+        .with_context(|| format!("due date '{}' is neither YYYY-MM-DD nor RFC 3339", value))
+}
+
+/// Exports cards with their text, tags, scheduling and deck name under a
+/// header, which `CsvImporter` maps back (leaving out the deck).
+pub struct CsvExporter;
+
+impl DeckExporter for CsvExporter {
+    fn name(&self) -> &'static str {
+        "CSV"
+    }
+
+    fn export(&self, decks: &[Deck], path: &Path) -> Result<usize> {
+        let mut writer = ::csv::Writer::from_path(path).with_context(|| format!("Failed to create {:?}", path))?;
+        writer.write_record(["id", "front", "back", "notes", "tags", "interval", "ease", "due", "deck"])?;
+
+        let mut count = 0;
+        for deck in decks {
+            for card in &deck.cards {
+                // New cards have no scheduling yet
+                let (interval, ease, due) = if card.is_new() {
+                    Default::default()
+                } else {
+                    (
+                        card.interval.to_string(),
+                        format!("{:.2}", card.ease_factor),
+                        card.due_date.map(|d| d.to_rfc3339()).unwrap_or_default(),
+                    )
+                };
+                writer.write_record([
+                    card.id.as_str(),
+                    &card.front,
+                    &card.back,
+                    &card.notes,
+                    &card.tags.join(" "),
+                    &interval,
+                    &ease,
+                    &due,
+                    &deck.name,
+                ])?;
+                count += 1;
+            }
+        }
+        writer.flush().with_context(|| format!("Failed to write {:?}", path))?;
+        Ok(count)
+    }
+}
@@ -0,0 +1,577 @@
+//! Markdown decks, one per file, for writing and reviewing decks as text.
+//!
+//! ```markdown
+//! ---
+//! name: Spanish Verbs
+//! description: Irregular verbs
+//! tags: [spanish, verbs]
+//! ---
+//!
+//! ## What does *ser* mean?
+//! To be, for lasting traits.
+//!
+//! Q: What does *estar* mean?
+//! A: To be, for states and places.
+//!
+//! *Ir* means?
+//! ---
+//! To go.
+//! ```
+//!
+//! A card is a `##` (or deeper) heading with its answer below, a `Q:` line
+//! with an `A:` line, or a paragraph and its answer around a `---` line. A
+//! `---` line always starts a card, taking the paragraph above it from the
+//! card before; `***` ends a card without starting another.
+//! `Tags:` and `Notes:` lines add tags and notes to the card they're in, and
+//! `<!-- id: ... -->` keeps its ID for update imports. Frontmatter tags go on
+//! every card. A `#` heading before the first card is the deck's title, and
+//! names it if the frontmatter doesn't; text before the first card is its
+//! description if the frontmatter has none. Nothing inside code fences is
+//! read as structure, and a backslash at the start of a line keeps it as text.
+//!
+//! Scheduling isn't stored; exported decks import as new cards, or keep
+//! their progress when imported with `ImportMode::Update`.
+
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use super::{filename_to_title_case, DeckExporter, DeckImporter, Imported};
+use crate::models::{Card, Deck};
+use crate::storage::is_valid_id;
+
+/// Deck-level settings from the frontmatter.
+#[derive(Debug, Default)]
+pub(super) struct Frontmatter {
+    pub(super) name: Option<String>,
+    pub(super) description: Option<String>,
+    pub(super) tags: Vec<String>,
+}
+
+/// Which structure started a card, which decides what ends its front.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    Heading,
+    Question,
+    /// Front and back separated by `---`.
+    Separated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part {
+    Front,
+    Back,
+    Notes,
+}
+
+/// A card as its lines are read.
+struct Draft {
+    line: usize,
+    style: Style,
+    part: Part,
+    front: Vec<String>,
+    back: Vec<String>,
+    notes: Vec<String>,
+    tags: Vec<String>,
+    id: Option<String>,
+}
+
+impl Draft {
+    fn new(line: usize, style: Style, part: Part, front: Vec<String>) -> Self {
+        Self {
+            line,
+            style,
+            part,
+            front,
+            back: Vec::new(),
+            notes: Vec::new(),
+            tags: Vec::new(),
+            id: None,
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        match self.part {
+            Part::Front => self.front.push(line),
+            Part::Back => self.back.push(line),
+            Part::Notes => self.notes.push(line),
+        }
+    }
+}
+
+/// Imports Markdown decks (`.md`), one deck per file.
+pub struct MarkdownImporter;
+
+impl MarkdownImporter {
+    /// Import a deck from a Markdown file. Parts that aren't cards, or
+    /// cards missing a side, are skipped and reported by line.
+    pub fn import_markdown(&self, path: &Path, deck_name: Option<&str>) -> Result<Imported> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        let fallback = match deck_name {
+            Some(name) => name.to_string(),
+            None => path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(filename_to_title_case)
+                .unwrap_or_else(|| "Imported Deck".to_string()),
+        };
+        parse(&content, &fallback)
+    }
+}
+
+impl DeckImporter for MarkdownImporter {
+    fn name(&self) -> &'static str {
+        "Markdown"
+    }
+
+    fn import(&self, path: &Path, deck_name: Option<&str>) -> Result<Imported> {
+        self.import_markdown(path, deck_name)
+    }
+}
+
+/// Parse a Markdown deck, naming it `fallback_name` if the file doesn't.
+fn parse(content: &str, fallback_name: &str) -> Result<Imported> {
+    let lines: Vec<&str> = content.lines().map(|l| l.trim_end()).collect();
+    let mut skipped = Vec::new();
+
+    let (frontmatter, start) = read_frontmatter(&lines, &mut skipped)?;
+
+    let mut title = None;
+    let mut cards: Vec<Draft> = Vec::new();
+    let mut draft: Option<Draft> = None;
+    // Text outside any card, and the line it starts on
+    let mut loose: Vec<String> = Vec::new();
+    let mut loose_line = 0;
+    let mut preamble: Vec<String> = Vec::new();
+    let mut in_fence = false;
+
+    for (i, raw) in lines.iter().enumerate().skip(start) {
+        let number = i + 1;
+        let line = raw.to_string();
+
+        if is_fence(raw) {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            if let Some((level, text)) = heading(raw) {
+                finish(&mut draft, &mut cards, &mut loose, loose_line, &mut preamble, &mut skipped);
+                if level == 1 && title.is_none() && cards.is_empty() {
+                    title = Some(text.to_string());
+                } else {
+                    draft = Some(Draft::new(number, Style::Heading, Part::Back, vec![text.to_string()]));
+                }
+                continue;
+            }
+            if let Some(text) = raw.strip_prefix("Q:") {
+                finish(&mut draft, &mut cards, &mut loose, loose_line, &mut preamble, &mut skipped);
+                draft = Some(Draft::new(number, Style::Question, Part::Front, vec![text.trim().to_string()]));
+                continue;
+            }
+            if *raw == "***" {
+                finish(&mut draft, &mut cards, &mut loose, loose_line, &mut preamble, &mut skipped);
+                continue;
+            }
+            if let Some(ref mut card) = draft {
+                if let Some(text) = raw.strip_prefix("A:").filter(|_| card.style == Style::Question && card.part == Part::Front) {
+                    card.part = Part::Back;
+                    card.push(text.trim().to_string());
+                    continue;
+                }
+                if let Some(id) = id_comment(raw) {
+                    card.id = Some(id.to_string());
+                    continue;
+                }
+                if let Some(tags) = raw.strip_prefix("Tags:") {
+                    card.tags.extend(split_tags(tags));
+                    continue;
+                }
+                if let Some(text) = raw.strip_prefix("Notes:") {
+                    card.part = Part::Notes;
+                    card.push(text.trim().to_string());
+                    continue;
+                }
+            }
+            if *raw == "---" {
+                // The paragraph above the line is the front of a new card
+                let above = match draft {
+                    Some(ref mut card) => match card.part {
+                        Part::Front => None,
+                        Part::Back => Some(&mut card.back),
+                        Part::Notes => Some(&mut card.notes),
+                    },
+                    None => Some(&mut loose),
+                };
+                if let Some(above) = above {
+                    let front = last_paragraph(above);
+                    if !front.is_empty() {
+                        let front_line = number - front.len();
+                        finish(&mut draft, &mut cards, &mut loose, loose_line, &mut preamble, &mut skipped);
+                        draft = Some(Draft::new(front_line, Style::Separated, Part::Back, front));
+                        continue;
+                    }
+                }
+            }
+        }
+
+        let line = if in_fence || is_fence(raw) { line } else { unescape(raw).to_string() };
+        match draft {
+            Some(ref mut card) => card.push(line),
+            None => {
+                if loose.iter().all(|l| l.is_empty()) {
+                    loose_line = number;
+                }
+                loose.push(line);
+            }
+        }
+    }
+    finish(&mut draft, &mut cards, &mut loose, loose_line, &mut preamble, &mut skipped);
+
+    let name = frontmatter.name.or(title).unwrap_or_else(|| fallback_name.to_string());
+    let mut deck = Deck::new(name);
+    deck.description = frontmatter.description.unwrap_or_else(|| join(&preamble));
+
+    let mut ids = HashSet::new();
+    for draft in cards {
+        let front = join(&draft.front);
+        let back = join(&draft.back);
+        if front.is_empty() {
+            skipped.push(format!("line {}: the front is empty", draft.line));
+            continue;
+        }
+        if back.is_empty() {
+            skipped.push(format!("line {}: '{}' has no answer", draft.line, first_line(&front)));
+            continue;
+        }
+
+        let mut card = Card::new(front, back);
+        card.notes = join(&draft.notes);
+        for tag in frontmatter.tags.iter().chain(&draft.tags) {
+            if !card.tags.contains(tag) {
+                card.tags.push(tag.clone());
+            }
+        }
+        if let Some(id) = draft.id {
+            if !is_valid_id(&id) {
+                skipped.push(format!("line {}: '{}' is not a valid card ID", draft.line, id));
+                continue;
+            }
+            card.id = id;
+        }
+        if !ids.insert(card.id.clone()) {
+            skipped.push(format!("line {}: ID '{}' is used by an earlier card", draft.line, card.id));
+            continue;
+        }
+        deck.cards.push(card);
+    }
+
+    Ok(Imported {
+        decks: vec![deck],
+        skipped,
+    })
+}
+
+/// End the card being read, if any, and the text outside cards before it:
+/// that becomes the deck description if no card came first.
+fn finish(
+    draft: &mut Option<Draft>,
+    cards: &mut Vec<Draft>,
+    loose: &mut Vec<String>,
+    loose_line: usize,
+    preamble: &mut Vec<String>,
+    skipped: &mut Vec<String>,
+) {
+    if !loose.iter().all(|l| l.is_empty()) {
+        if cards.is_empty() && draft.is_none() {
+            if !preamble.is_empty() {
+                preamble.push(String::new());
+            }
+            preamble.append(loose);
+        } else {
+            skipped.push(format!("line {}: text outside a card", loose_line));
+        }
+    }
+    loose.clear();
+    cards.extend(draft.take());
+}
+
+/// The frontmatter between `---` lines at the very top, if any, and the
+/// index of the first line after it.
+pub(super) fn read_frontmatter(lines: &[&str], skipped: &mut Vec<String>) -> Result<(Frontmatter, usize)> {
+    if lines.first().map(|l| l.trim_end()) != Some("---") {
+        return Ok((Frontmatter::default(), 0));
+    }
+    let end = lines[1..]
+        .iter()
+        .position(|l| matches!(l.trim_end(), "---" | "..."))
+        .context("The frontmatter that starts on line 1 is never closed with '---'")?;
+    Ok((parse_frontmatter(&lines[1..=end], skipped), end + 2))
+}
+
+/// Read the frontmatter's `key: value` lines. Values may be quoted, `|`
+/// blocks of indented lines, or for tags `[a, b]` or `- item` lists.
+fn parse_frontmatter(lines: &[&str], skipped: &mut Vec<String>) -> Frontmatter {
+    let mut frontmatter = Frontmatter::default();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let number = i + 2;
+        i += 1;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            skipped.push(format!("line {}: frontmatter line '{}' is not 'key: value'", number, line));
+            continue;
+        };
+        let value = value.trim();
+
+        // Indented lines following the key belong to it
+        let mut block = Vec::new();
+        while i < lines.len()
+            && (lines[i].starts_with([' ', '\t']) || lines[i].starts_with("- ") || lines[i].is_empty())
+        {
+            block.push(lines[i].trim());
+            i += 1;
+        }
+        while block.last() == Some(&"") {
+            block.pop();
+        }
+
+        match key.trim().to_lowercase().as_str() {
+            "name" | "title" => frontmatter.name = Some(unquote(value)),
+            "description" => {
+                frontmatter.description = Some(match value {
+                    "|" => block.join("\n"),
+                    ">" => block.join(" "),
+                    _ => unquote(value),
+                })
+            }
+            "tags" => {
+                frontmatter.tags = if value.is_empty() {
+                    block
+                        .iter()
+                        .filter_map(|item| item.strip_prefix('-'))
+                        .flat_map(split_tags)
+                        .collect()
+                } else {
+                    split_tags(value.trim_start_matches('[').trim_end_matches(']'))
+                }
+            }
+            _ => {}
+        }
+    }
+    frontmatter
+}
+
+/// Split tags separated by commas or spaces, dropping quotes and `#`.
+fn split_tags(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .map(|tag| unquote(tag).trim_start_matches('#').to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// A YAML scalar without its quotes.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        let mut out = String::new();
+        let mut chars = value[1..value.len() - 1].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') => out.push('\n'),
+                    Some(other) => out.push(other),
+                    None => {}
+                },
+                _ => out.push(c),
+            }
+        }
+        out
+    } else if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        value[1..value.len() - 1].replace("''", "'")
+    } else {
+        value.to_string()
+    }
+}
+
+/// An ATX heading's level and text, without a closing `#` sequence.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    let text = rest.trim();
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.ends_with(' ') => stripped.trim_end(),
+        _ => text,
+    };
+    Some((level, text))
+}
+
+pub(super) fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// The ID in an `<!-- id: ... -->` comment.
+fn id_comment(line: &str) -> Option<&str> {
+    let id = line.strip_prefix("<!--")?.strip_suffix("-->")?.trim().strip_prefix("id:")?.trim();
+    (!id.is_empty()).then_some(id)
+}
+
+/// Whether a line of card text would be read as structure, and so needs a
+/// backslash in front to stay text.
+fn is_structural(line: &str) -> bool {
+    heading(line).is_some()
+        || line == "---"
+        || line == "***"
+        || id_comment(line).is_some()
+        || ["Q:", "A:", "Tags:", "Notes:", "\\"].iter().any(|p| line.starts_with(p))
+}
+
+fn unescape(line: &str) -> &str {
+    match line.strip_prefix('\\') {
+        Some(rest) if is_structural(rest) => rest,
+        _ => line,
+    }
+}
+
+/// Remove the lines after the last blank line from `lines`, returning them.
+fn last_paragraph(lines: &mut Vec<String>) -> Vec<String> {
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    let start = lines.iter().rposition(|l| l.is_empty()).map_or(0, |i| i + 1);
+    lines.split_off(start)
+}
+
+/// Lines joined into card text, without blank lines around it.
+fn join(lines: &[String]) -> String {
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+/// Exports decks as Markdown: to the file at the path for one deck, or to
+/// a file per deck in the directory at the path for several.
+pub struct MarkdownExporter;
+
+impl MarkdownExporter {
+    /// A deck as Markdown, cards as headings (or `Q:`/`A:` for fronts of
+    /// several lines) with their IDs.
+    fn to_markdown(deck: &Deck) -> String {
+        let mut out = String::from("---\n");
+        let _ = writeln!(out, "name: {}", yaml_scalar(&deck.name));
+        if deck.description.contains('\n') {
+            out.push_str("description: |\n");
+            for line in deck.description.lines() {
+                let _ = writeln!(out, "  {}", line);
+            }
+        } else if !deck.description.is_empty() {
+            let _ = writeln!(out, "description: {}", yaml_scalar(&deck.description));
+        }
+        out.push_str("---\n");
+
+        for card in &deck.cards {
+            out.push('\n');
+            let single_line = !card.front.contains('\n') && !card.front.ends_with('#') && !card.front.trim().is_empty();
+            if single_line {
+                let _ = writeln!(out, "## {}", card.front.trim());
+                let _ = writeln!(out, "<!-- id: {} -->", card.id);
+                out.push('\n');
+                write_text(&mut out, "", &card.back);
+            } else {
+                write_text(&mut out, "Q: ", &card.front);
+                let _ = writeln!(out, "<!-- id: {} -->", card.id);
+                write_text(&mut out, "A: ", &card.back);
+            }
+            if !card.tags.is_empty() {
+                let _ = writeln!(out, "\nTags: {}", card.tags.join(" "));
+            }
+            if !card.notes.is_empty() {
+                out.push('\n');
+                write_text(&mut out, "Notes: ", &card.notes);
+            }
+        }
+        out
+    }
+}
+
+/// Write card text with `prefix` before its first line, escaping lines
+/// outside code fences that would otherwise be read as structure.
+fn write_text(out: &mut String, prefix: &str, text: &str) {
+    let mut in_fence = false;
+    for (i, line) in text.lines().enumerate() {
+        // Text after a prefix isn't at the start of the line
+        let escape = !in_fence && (i > 0 || prefix.is_empty()) && is_structural(line);
+        if is_fence(line) {
+            in_fence = !in_fence;
+        }
+        let line_prefix = if i == 0 { prefix } else { "" };
+        let _ = writeln!(out, "{}{}{}", line_prefix, if escape { "\\" } else { "" }, line);
+    }
+    if text.is_empty() {
+        let _ = writeln!(out, "{}", prefix.trim_end());
+    }
+}
+
+/// A YAML scalar, quoted if it would otherwise read as something else.
+fn yaml_scalar(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.starts_with(|c: char| "\"'#[]{}|>-&*!%@`,?:".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+    }
+}
+
+/// A file name for a deck: its name in lowercase, words joined by `-`.
+fn file_stem(name: &str) -> String {
+    let stem = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    if stem.is_empty() {
+        "deck".to_string()
+    } else {
+        stem
+    }
+}
+
+impl DeckExporter for MarkdownExporter {
+    fn name(&self) -> &'static str {
+        "Markdown"
+    }
+
+    fn export(&self, decks: &[Deck], path: &Path) -> Result<usize> {
+        if let [deck] = decks {
+            fs::write(path, Self::to_markdown(deck)).with_context(|| format!("Failed to write {:?}", path))?;
+            return Ok(deck.cards.len());
+        }
+
+        fs::create_dir_all(path).with_context(|| format!("Failed to create {:?}", path))?;
+        let mut used: HashMap<String, usize> = HashMap::new();
+        let mut count = 0;
+        for deck in decks {
+            let stem = file_stem(&deck.name);
+            let n = used.entry(stem.clone()).or_insert(0);
+            *n += 1;
+            let file = if *n == 1 { format!("{}.md", stem) } else { format!("{}-{}.md", stem, n) };
+            let file = path.join(file);
+            fs::write(&file, Self::to_markdown(deck)).with_context(|| format!("Failed to write {:?}", file))?;
+            count += deck.cards.len();
+        }
+        Ok(count)
+    }
+}
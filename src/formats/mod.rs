@@ -0,0 +1,128 @@
+//! Import and export of decks in external file formats.
+//!
+//! Each format implements `DeckImporter` and/or `DeckExporter`; storage only
+//! deals with `Deck` values, so new formats don't touch the storage backends.
+
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+use crate::models::Deck;
+use crate::storage::DeckStorage;
+
+mod anki;
+mod csv;
+mod markdown;
+mod update;
+mod vault;
+
+pub use anki::{AnkiImporter, AnkiMedia, AnkiPackageExporter};
+pub use csv::{parse_delimiter, CsvColumn, CsvExporter, CsvImporter};
+pub use markdown::{MarkdownExporter, MarkdownImporter};
+pub use update::{save_imported, ImportMode, ImportOptions, Saved, UpdateReport};
+pub use vault::VaultImporter;
+
+/// Decks read from a file, and the parts of it that were left out.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub decks: Vec<Deck>,
+    /// Why each left-out part (such as a malformed row) was skipped.
+    pub skipped: Vec<String>,
+}
+
+/// Reads decks from a file in an external format.
+pub trait DeckImporter {
+    /// Human-readable format name.
+    fn name(&self) -> &'static str;
+
+    /// Parse a file into decks.
+    /// `deck_name` names the deck for formats that don't carry their own.
+    fn import(&self, path: &Path, deck_name: Option<&str>) -> Result<Imported>;
+}
+
+/// Writes decks to a file in an external format.
+pub trait DeckExporter {
+    /// Human-readable format name.
+    fn name(&self) -> &'static str;
+
+    /// Write decks to a file, returning the number of cards written.
+    fn export(&self, decks: &[Deck], path: &Path) -> Result<usize>;
+}
+
+/// Decks imported from a folder, by name.
+#[derive(Debug, Default)]
+pub struct FolderImport {
+    /// New decks, with their card counts.
+    pub imported: Vec<(String, usize)>,
+    pub updated: Vec<(String, UpdateReport)>,
+    /// Decks left alone because they already exist.
+    pub skipped: Vec<String>,
+}
+
+/// Import all files with `extension` from a folder with `importer`.
+/// Names decks based on filename, converting snake_case/kebab-case to Title Case,
+/// unless the format names its own.
+/// `options` decide what happens to decks whose name already exists.
+pub fn import_folder(
+    storage: &DeckStorage,
+    folder_path: &Path,
+    importer: &dyn DeckImporter,
+    extension: &str,
+    options: &ImportOptions,
+) -> Result<FolderImport> {
+    let mut report = FolderImport::default();
+
+    for entry in fs::read_dir(folder_path)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if path.extension().map_or(false, |e| e == extension) {
+            let deck_name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(filename_to_title_case)
+                .unwrap_or_else(|| "Imported Deck".to_string());
+
+            match importer.import(&path, Some(&deck_name)) {
+                Ok(result) => {
+                    for reason in &result.skipped {
+                        eprintln!("Warning: Skipped {:?} {}", path, reason);
+                    }
+                    for deck in result.decks {
+                        if deck.cards.is_empty() {
+                            continue;
+                        }
+                        let card_count = deck.cards.len();
+                        match save_imported(storage, deck, options)? {
+                            (deck, Saved::Created) => report.imported.push((deck.name, card_count)),
+                            (deck, Saved::Updated(changes)) => report.updated.push((deck.name, changes)),
+                            (deck, Saved::Skipped) => report.skipped.push(deck.name),
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Warning: Failed to import {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Convert a filename (snake_case or kebab-case) to Title Case.
+fn filename_to_title_case(name: &str) -> String {
+    name.split(|c| c == '_' || c == '-')
+        .filter(|s| !s.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(first) => {
+                    first.to_uppercase().collect::<String>() + chars.as_str().to_lowercase().as_str()
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,173 @@
+//! Importing into decks that already exist.
+//!
+//! An update import matches the file's cards to the deck's by ID, or else by
+//! front text compared ignoring case and spacing. Matched cards take the new
+//! text but keep their scheduling; unmatched ones are added.
+
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::models::{Card, Deck};
+use crate::storage::DeckStorage;
+
+/// What an import does with a deck whose name is already taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportMode {
+    /// Leave the existing deck alone
+    #[default]
+    Skip,
+    /// Add new cards to the existing deck and update changed ones, keeping their progress
+    Update,
+}
+
+/// What an update import changed in a deck.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UpdateReport {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    /// Cards no longer in the file, if those were removed.
+    pub removed: usize,
+}
+
+impl fmt::Display for UpdateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} added, {} updated, {} unchanged", self.added, self.updated, self.unchanged)?;
+        if self.removed > 0 {
+            write!(f, ", {} removed", self.removed)?;
+        }
+        Ok(())
+    }
+}
+
+/// What saving an imported deck did.
+#[derive(Debug)]
+pub enum Saved {
+    Created,
+    Updated(UpdateReport),
+    /// A deck of that name exists and the mode is `Skip`.
+    Skipped,
+}
+
+/// How an import treats existing decks, and whether it saves anything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportOptions {
+    pub mode: ImportMode,
+    /// In `Update` mode, delete cards that are no longer in the file.
+    pub remove_missing: bool,
+    /// Work out what the import would do without saving anything.
+    pub dry_run: bool,
+}
+
+/// Save an imported deck as a new deck, or, in `Update` mode, into the
+/// existing deck of the same name. Returns the deck as saved, which for
+/// updates is the existing deck with the changes, or the import if skipped.
+/// A dry run returns the same without saving.
+pub fn save_imported(storage: &DeckStorage, imported: Deck, options: &ImportOptions) -> Result<(Deck, Saved)> {
+    let name = imported.name.to_lowercase();
+    let existing = storage.list_decks()?.into_iter().find(|d| d.name.to_lowercase() == name);
+    let (deck, saved) = match existing {
+        None => (imported, Saved::Created),
+        Some(_) if options.mode == ImportMode::Skip => return Ok((imported, Saved::Skipped)),
+        Some(info) => {
+            let mut deck = storage
+                .load_deck(&info.id)?
+                .with_context(|| format!("Deck '{}' no longer exists", info.name))?;
+            let report = update_deck(&mut deck, imported, options.remove_missing);
+            (deck, Saved::Updated(report))
+        }
+    };
+    if !options.dry_run {
+        storage.save_deck(&deck)?;
+    }
+    Ok((deck, saved))
+}
+
+/// Front text as cards are matched by: lowercase, with runs of whitespace
+/// collapsed.
+pub(super) fn match_key(front: &str) -> String {
+    front.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Update `deck` with the cards of `imported`. With `remove_missing`, cards
+/// that none of the imported ones matched are deleted.
+fn update_deck(deck: &mut Deck, imported: Deck, remove_missing: bool) -> UpdateReport {
+    let mut report = UpdateReport::default();
+    let by_id: HashMap<String, usize> = deck.cards.iter().enumerate().map(|(i, c)| (c.id.clone(), i)).collect();
+    let mut by_front: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, card) in deck.cards.iter().enumerate().rev() {
+        by_front.entry(match_key(&card.front)).or_default().push(i);
+    }
+
+    let existing = deck.cards.len();
+    let mut matched = HashSet::new();
+    for card in imported.cards {
+        // Each card is matched at most once, so repeated fronts stay apart
+        let found = by_id.get(&card.id).copied().filter(|i| !matched.contains(i)).or_else(|| {
+            let candidates = by_front.get_mut(&match_key(&card.front))?;
+            while let Some(i) = candidates.pop() {
+                if !matched.contains(&i) {
+                    return Some(i);
+                }
+            }
+            None
+        });
+
+        match found {
+            Some(i) => {
+                matched.insert(i);
+                if update_content(&mut deck.cards[i], card) {
+                    report.updated += 1;
+                } else {
+                    report.unchanged += 1;
+                }
+            }
+            None => {
+                // The ID may belong to a card already matched by front text
+                let card = if by_id.contains_key(&card.id) {
+                    Card { id: crate::models::new_id(), ..card }
+                } else {
+                    card
+                };
+                deck.cards.push(card);
+                report.added += 1;
+            }
+        }
+    }
+
+    if remove_missing {
+        let mut i = 0;
+        deck.cards.retain(|_| {
+            let keep = i >= existing || matched.contains(&i);
+            i += 1;
+            keep
+        });
+        report.removed = existing - matched.len();
+    }
+    report
+}
+
+/// Copy the imported card's content onto `card`, keeping its scheduling.
+/// Notes and tags the import leaves empty stay as they are. Returns whether
+/// anything changed.
+fn update_content(card: &mut Card, imported: Card) -> bool {
+    let mut changed = false;
+    if card.front != imported.front {
+        card.front = imported.front;
+        changed = true;
+    }
+    if card.back != imported.back {
+        card.back = imported.back;
+        changed = true;
+    }
+    if !imported.notes.is_empty() && card.notes != imported.notes {
+        card.notes = imported.notes;
+        changed = true;
+    }
+    if !imported.tags.is_empty() && card.tags != imported.tags {
+        card.tags = imported.tags;
+        changed = true;
+    }
+    changed
+}
@@ -0,0 +1,486 @@
+//! Flashcards written inline in Obsidian vaults and Logseq graphs.
+//!
+//! In an Obsidian vault, notes tagged `#flashcards` (in the text or the
+//! frontmatter) are read for `question::answer` lines, and
+//! `question:::answer` lines, which make a card each way. Scheduling the
+//! Spaced Repetition plugin left in `<!--SR:...-->` comments is kept.
+//!
+//! A folder holding `logseq/config.edn` is read as a Logseq graph instead:
+//! blocks tagged `#card` are the fronts and their child blocks the backs,
+//! with scheduling from their `card-*` properties. Other `key:: value`
+//! lines there are properties, not cards.
+//!
+//! Each folder is a deck, named `Vault::Folder::Subfolder` after the vault
+//! folder (Logseq namespaces count as folders). Card IDs come from the
+//! note's path and the card's front as update imports match it, numbered
+//! when a note has the same front twice, so re-importing in update mode keeps
+//! progress through edits to the answers and cards added or moved anywhere.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::csv::parse_due;
+use super::markdown::{is_fence, read_frontmatter};
+use super::update::match_key;
+use super::{DeckImporter, Imported};
+use crate::models::{Card, Deck};
+
+/// Tag marking notes with cards in Obsidian.
+const OBSIDIAN_TAG: &str = "flashcards";
+/// Tag marking card blocks in Logseq.
+const LOGSEQ_TAG: &str = "card";
+
+/// Scheduling a card had in the app it was written for.
+#[derive(Debug, Default, Clone, Copy)]
+struct Schedule {
+    due: Option<DateTime<Local>>,
+    interval: u32,
+    ease: Option<f64>,
+    repetitions: Option<u32>,
+}
+
+/// A card found in a note.
+struct Found {
+    front: String,
+    back: String,
+    schedule: Option<Schedule>,
+}
+
+/// Imports the cards in an Obsidian vault or Logseq graph, a deck per folder.
+pub struct VaultImporter;
+
+impl VaultImporter {
+    /// Import the cards of the vault at `root`, naming its decks after
+    /// `vault_name`, or else the vault's folder. Notes that can't be read
+    /// and cards missing a side are skipped and reported.
+    pub fn import_vault(&self, root: &Path, vault_name: Option<&str>) -> Result<Imported> {
+        if !root.is_dir() {
+            anyhow::bail!("{:?} is not a folder", root);
+        }
+        let vault_name = match vault_name {
+            Some(name) => name.to_string(),
+            None => root
+                .canonicalize()
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+                .unwrap_or_else(|| "Vault".to_string()),
+        };
+        let logseq = root.join("logseq").join("config.edn").is_file();
+
+        let mut notes = Vec::new();
+        collect_notes(root, root, logseq, &mut notes)?;
+        notes.sort();
+
+        let mut imported = Imported::default();
+        let mut deck_index: HashMap<String, usize> = HashMap::new();
+        for relative in notes {
+            let label = relative.to_string_lossy().replace('\\', "/");
+            let content = match fs::read_to_string(root.join(&relative)) {
+                Ok(content) => content,
+                Err(e) => {
+                    imported.skipped.push(format!("{}: {}", label, e));
+                    continue;
+                }
+            };
+
+            let mut skipped = Vec::new();
+            let found = if logseq {
+                logseq_cards(&content, &mut skipped)
+            } else {
+                obsidian_cards(&content, &mut skipped)?
+            };
+            imported.skipped.extend(skipped.into_iter().map(|reason| format!("{} {}", label, reason)));
+            if found.is_empty() {
+                continue;
+            }
+
+            let deck_name = deck_name(&vault_name, &relative, logseq);
+            let i = *deck_index.entry(deck_name.clone()).or_insert_with(|| {
+                imported.decks.push(Deck::new(deck_name));
+                imported.decks.len() - 1
+            });
+
+            // Numbers fronts that repeat within the note, in order
+            let mut seen: HashMap<String, usize> = HashMap::new();
+            for found in found {
+                let key = match_key(&found.front);
+                let n = seen.entry(key.clone()).or_insert(0);
+                *n += 1;
+                let mut card = Card::new(found.front, found.back);
+                card.id = stable_id(&[&label, &key, &n.to_string()]);
+                if let Some(schedule) = found.schedule {
+                    apply_schedule(&mut card, schedule);
+                }
+                imported.decks[i].cards.push(card);
+            }
+        }
+        Ok(imported)
+    }
+}
+
+impl DeckImporter for VaultImporter {
+    fn name(&self) -> &'static str {
+        "Vault"
+    }
+
+    fn import(&self, path: &Path, deck_name: Option<&str>) -> Result<Imported> {
+        self.import_vault(path, deck_name)
+    }
+}
+
+/// Add the paths, relative to `root`, of the notes under `dir`. Hidden
+/// folders (like `.obsidian` and `.trash`) and Logseq's own folder are left
+/// out, as are symlinked folders, which could lead back up the tree; linked
+/// notes are read.
+fn collect_notes(root: &Path, dir: &Path, logseq: bool, notes: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        // Unlike `path.is_dir()`, doesn't follow symlinks
+        if entry.file_type()?.is_dir() {
+            if !(logseq && dir == root && name == "logseq") {
+                collect_notes(root, &path, logseq, notes)?;
+            }
+        } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("md")) && path.is_file() {
+            if let Ok(relative) = path.strip_prefix(root) {
+                notes.push(relative.to_path_buf());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The deck for a note: the vault name, then its folders. Logseq keeps
+/// pages in one folder, so their namespaces (`a___b.md` or `a%2Fb.md` for
+/// page `a/b`) stand in for folders, and journals go in the vault's deck.
+fn deck_name(vault_name: &str, relative: &Path, logseq: bool) -> String {
+    let mut parts = vec![vault_name.to_string()];
+    let folders: Vec<String> = relative
+        .parent()
+        .into_iter()
+        .flat_map(|p| p.components())
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+
+    match folders.first().map(String::as_str) {
+        Some("pages") if logseq && folders.len() == 1 => {
+            let stem = relative.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+            let page = stem.replace("%2F", "/").replace("%2f", "/").replace("___", "/");
+            if let Some((namespace, _)) = page.rsplit_once('/') {
+                parts.extend(namespace.split('/').map(String::from));
+            }
+        }
+        Some("journals") if logseq => {}
+        _ => parts.extend(folders),
+    }
+    parts.join("::")
+}
+
+/// A card ID made from `parts`: the same parts always give the same ID.
+/// This is 128-bit FNV-1a, in hex like other IDs.
+fn stable_id(parts: &[&str]) -> String {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    let mut hash = OFFSET;
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            // Keeps ["ab", "c"] apart from ["a", "bc"]
+            hash = (hash ^ 0x1f).wrapping_mul(PRIME);
+        }
+        for byte in part.bytes() {
+            hash = (hash ^ u128::from(byte)).wrapping_mul(PRIME);
+        }
+    }
+    format!("{:032x}", hash)
+}
+
+fn apply_schedule(card: &mut Card, schedule: Schedule) {
+    if let Some(ease) = schedule.ease.filter(|e| (1.3..=10.0).contains(e)) {
+        card.ease_factor = ease;
+    }
+    if schedule.interval > 0 {
+        // As for CSV imports, cards with an interval have graduated
+        card.interval = schedule.interval;
+        card.repetitions = schedule.repetitions.unwrap_or(2).max(2);
+        card.due_date = Some(
+            schedule
+                .due
+                .unwrap_or_else(|| Local::now() + Duration::days(schedule.interval.into())),
+        );
+    } else {
+        card.due_date = schedule.due;
+    }
+}
+
+// ══════════════════════════════════════════════════════════════════════════
+// Obsidian
+// ══════════════════════════════════════════════════════════════════════════
+
+/// The `::` and `:::` cards of a note tagged `#flashcards`.
+fn obsidian_cards(content: &str, skipped: &mut Vec<String>) -> Result<Vec<Found>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut frontmatter_skipped = Vec::new();
+    let (frontmatter, start) = read_frontmatter(&lines, &mut frontmatter_skipped)?;
+
+    let tagged = frontmatter.tags.iter().any(|t| is_tag(t, OBSIDIAN_TAG))
+        || body_lines(&lines, start).any(|(_, line)| has_hashtag(line, OBSIDIAN_TAG));
+    if !tagged {
+        return Ok(Vec::new());
+    }
+
+    let mut cards = Vec::new();
+    for (i, line) in body_lines(&lines, start) {
+        let Some((at, reversible)) = find_separator(line) else {
+            continue;
+        };
+        let separator_len = if reversible { 3 } else { 2 };
+        let front = clean_front(&line[..at]);
+        let (back, schedules) = split_sr_comment(&line[at + separator_len..]);
+        if front.is_empty() || back.is_empty() {
+            skipped.push(format!("line {}: a side of the card is empty", i + 1));
+            continue;
+        }
+
+        cards.push(Found {
+            front: front.clone(),
+            back: back.clone(),
+            schedule: schedules.first().copied(),
+        });
+        if reversible {
+            cards.push(Found {
+                front: back,
+                back: front,
+                schedule: schedules.get(1).copied(),
+            });
+        }
+    }
+    Ok(cards)
+}
+
+/// Lines after the frontmatter that aren't in code fences, with their index.
+fn body_lines<'a>(lines: &'a [&'a str], start: usize) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+    let mut in_fence = false;
+    lines.iter().enumerate().skip(start).filter_map(move |(i, line)| {
+        if is_fence(line) {
+            in_fence = !in_fence;
+            return None;
+        }
+        (!in_fence).then_some((i, *line))
+    })
+}
+
+/// Whether `tag` is `name` or one of its subtags, like `flashcards/spanish`.
+fn is_tag(tag: &str, name: &str) -> bool {
+    let tag = tag.trim_start_matches('#');
+    tag.eq_ignore_ascii_case(name)
+        || tag
+            .get(..name.len() + 1)
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{}/", name)))
+}
+
+/// Whether the line has `#name` (or a subtag) as a word.
+fn has_hashtag(line: &str, name: &str) -> bool {
+    line.split(|c: char| c.is_whitespace() || c == ',')
+        .any(|word| word.starts_with('#') && is_tag(word, name))
+}
+
+/// Where the card separator in a line is, if it has one outside inline
+/// code, and whether it's the reversible `:::`.
+fn find_separator(line: &str) -> Option<(usize, bool)> {
+    let bytes = line.as_bytes();
+    let mut in_code = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => in_code = !in_code,
+            b':' if !in_code && bytes.get(i + 1) == Some(&b':') => {
+                return Some((i, bytes.get(i + 2) == Some(&b':')));
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// A card front without its list marker and `#flashcards` tags.
+fn clean_front(text: &str) -> String {
+    let text = text.trim();
+    let text = text.strip_prefix("> ").unwrap_or(text);
+    let text = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| text.strip_prefix(marker))
+        .or_else(|| {
+            let digits = text.chars().take_while(char::is_ascii_digit).count();
+            text[digits..].strip_prefix(". ").filter(|_| digits > 0)
+        })
+        .unwrap_or(text);
+    text.split(' ')
+        .filter(|word| !(word.starts_with('#') && is_tag(word, OBSIDIAN_TAG)))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim()
+        .to_string()
+}
+
+/// A card back without the Spaced Repetition plugin's scheduling comment,
+/// and the schedules in it: `<!--SR:!2024-03-01,12,270-->`, with a second
+/// `!date,interval,ease` for the reverse card.
+fn split_sr_comment(text: &str) -> (String, Vec<Schedule>) {
+    let Some(start) = text.find("<!--SR:") else {
+        return (text.trim().to_string(), Vec::new());
+    };
+    let comment = &text[start + "<!--SR:".len()..];
+    let comment = comment.split("-->").next().unwrap_or_default();
+    let schedules = comment
+        .split('!')
+        .filter(|s| !s.is_empty())
+        .filter_map(|entry| {
+            let mut fields = entry.split(',').map(str::trim);
+            let due = parse_due(fields.next()?).ok()?;
+            let interval = fields.next()?.parse().ok()?;
+            let ease = fields.next()?.parse::<f64>().ok()? / 100.0;
+            Some(Schedule {
+                due: Some(due),
+                interval,
+                ease: Some(ease),
+                repetitions: None,
+            })
+        })
+        .collect();
+    (text[..start].trim().to_string(), schedules)
+}
+
+// ══════════════════════════════════════════════════════════════════════════
+// Logseq
+// ══════════════════════════════════════════════════════════════════════════
+
+/// Indentation width of a line, counting a tab as two spaces.
+fn indent_of(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 2 } else { 1 })
+        .sum()
+}
+
+/// A Logseq `key:: value` property line, which also holds SRS scheduling.
+fn property(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.trim().split_once(":: ").or_else(|| line.trim().strip_suffix("::").map(|k| (k, "")))?;
+    key.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        .then_some((key, value.trim()))
+}
+
+/// The blocks tagged `#card` in a page, with their child blocks as backs.
+fn logseq_cards(content: &str, skipped: &mut Vec<String>) -> Vec<Found> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut cards = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let bullet = line.trim_start().strip_prefix("- ");
+        let Some(text) = bullet.filter(|t| has_hashtag(t, LOGSEQ_TAG) || t.contains("[[card]]")) else {
+            i += 1;
+            continue;
+        };
+        let start = i;
+        let indent = indent_of(line);
+        i += 1;
+
+        let mut front = vec![strip_card_tag(text)];
+        let mut schedule = Schedule::default();
+        let mut scheduled = false;
+        // The block's own lines: more of the front, or properties
+        while i < lines.len() && indent_of(lines[i]) > indent && !lines[i].trim_start().starts_with("- ") {
+            match property(lines[i]) {
+                Some((key, value)) => scheduled |= read_srs_property(&mut schedule, key, value),
+                None => front.push(lines[i].trim().to_string()),
+            }
+            i += 1;
+        }
+
+        // Child blocks, down to the next block at this level or above
+        let mut children = Vec::new();
+        let mut in_fence = false;
+        while i < lines.len() && (in_fence || lines[i].trim().is_empty() || indent_of(lines[i]) > indent) {
+            if is_fence(lines[i]) {
+                in_fence = !in_fence;
+            }
+            if in_fence || property(lines[i]).is_none() {
+                children.push(lines[i]);
+            }
+            i += 1;
+        }
+
+        let front = front.join("\n").trim().to_string();
+        let back = child_text(&children);
+        if front.is_empty() || back.is_empty() {
+            skipped.push(format!("line {}: a side of the card is empty", start + 1));
+            continue;
+        }
+        cards.push(Found {
+            front,
+            back,
+            schedule: scheduled.then_some(schedule),
+        });
+    }
+    cards
+}
+
+/// Block text without its card tag.
+fn strip_card_tag(text: &str) -> String {
+    text.replace("[[card]]", "")
+        .split(' ')
+        .filter(|word| !(word.starts_with('#') && is_tag(word, LOGSEQ_TAG)))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim()
+        .to_string()
+}
+
+/// Read one of the `card-*` properties Logseq schedules cards with.
+/// Returns whether it was one.
+fn read_srs_property(schedule: &mut Schedule, key: &str, value: &str) -> bool {
+    match key {
+        "card-next-schedule" => schedule.due = parse_due(value).ok(),
+        "card-last-interval" => schedule.interval = value.parse::<f64>().map_or(0, |days| days.round().max(0.0) as u32),
+        "card-ease-factor" => schedule.ease = value.parse().ok(),
+        "card-repeats" => schedule.repetitions = value.parse().ok(),
+        _ => return false,
+    }
+    true
+}
+
+/// Child blocks as card text: a single block as plain text, several as a
+/// list, nested as they were.
+fn child_text(children: &[&str]) -> String {
+    let lines: Vec<&str> = children.iter().copied().filter(|l| !l.trim().is_empty()).collect();
+    let Some(min_indent) = lines.iter().map(|l| indent_of(l)).min() else {
+        return String::new();
+    };
+    let dedented: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            let rest = line.trim_start();
+            format!("{}{}", " ".repeat(indent_of(line) - min_indent), rest)
+        })
+        .collect();
+
+    let blocks = dedented.iter().filter(|l| l.starts_with("- ")).count();
+    if blocks == 1 && dedented[0].starts_with("- ") && dedented.iter().skip(1).all(|l| !l.trim_start().starts_with("- ")) {
+        // One block: its text, with continuation lines unindented
+        let mut text = vec![dedented[0][2..].to_string()];
+        text.extend(dedented.iter().skip(1).map(|l| l.strip_prefix("  ").unwrap_or(l.trim_start()).to_string()));
+        return text.join("\n");
+    }
+    dedented.join("\n")
+}
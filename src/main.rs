@@ -3,15 +3,18 @@
 //! A beautiful terminal-based flashcard application with SM-2 spaced repetition.
 
 mod config;
+mod formats;
+mod markup;
 mod models;
 mod sm2;
 mod storage;
+mod sync;
 mod ui;
 
 use std::io;
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
@@ -20,8 +23,15 @@ use crossterm::{
 };
 use ratatui::prelude::*;
 
-use storage::DeckStorage;
+use config::{Config, StorageBackend};
+use formats::{
+    AnkiImporter, AnkiPackageExporter, CsvColumn, CsvImporter, DeckExporter, DeckImporter, FolderImport,
+    ImportMode, ImportOptions, MarkdownExporter, MarkdownImporter, Saved, VaultImporter,
+};
+use storage::{BackupRotation, DeckStorage, Locked, RestoreMode, SqliteRepository};
+use ui::theme::Theme;
 use ui::App;
+use zeroize::Zeroizing;
 
 // ══════════════════════════════════════════════════════════════════════════
 // CLI Arguments
@@ -30,6 +40,9 @@ use ui::App;
 #[derive(Parser, Debug)]
 #[command(name = "flashcards")]
 #[command(author, version, about = "Anki-style spaced repetition flashcard TUI", long_about = None)]
+#[command(group(clap::ArgGroup::new("any_import").args(["import", "import_folder", "import_anki", "import_markdown", "import_vault", "import_backup"]).multiple(true)))]
+#[command(group(clap::ArgGroup::new("csv_import").args(["import", "import_folder"]).multiple(true)))]
+#[command(group(clap::ArgGroup::new("export").args(["export_backup", "export_anki", "export_markdown"]).multiple(true)))]
 struct Args {
     /// Directory containing deck files
     #[arg(short, long)]
@@ -47,27 +60,137 @@ struct Args {
     #[arg(long, default_value = "Imported Deck")]
     import_name: String,
 
-    /// Export all decks to a backup file
+    /// Field delimiter for --import and --import-folder: one character, or "tab"
+    #[arg(long, value_name = "CHAR", requires = "csv_import")]
+    csv_delimiter: Option<String>,
+
+    /// Card field of each CSV column in order, e.g. "front,back,-,tags"
+    /// (front, back, notes, tags, interval, ease, due, or - to skip);
+    /// defaults to the header row, or front,back without one
+    #[arg(long, value_name = "LIST", requires = "csv_import")]
+    csv_columns: Option<String>,
+
+    /// Export all decks (or those picked with --deck and --tag) to a backup file
     #[arg(short = 'x', long)]
     export_backup: Option<PathBuf>,
 
+    /// Encrypt the --export-backup file with a passphrase
+    #[arg(long, requires = "export_backup")]
+    encrypt_backup: bool,
+
     /// Import decks from a backup file
     #[arg(short = 'b', long)]
     import_backup: Option<PathBuf>,
 
-    /// Import from Anki export (.apkg or tab-separated .txt)
+    /// What --import-backup does with decks that already exist
+    #[arg(long, value_enum, default_value = "skip", requires = "import_backup")]
+    restore_mode: RestoreMode,
+
+    /// What imports do with decks that already exist [default: skip, or
+    /// update for --import-vault]
+    #[arg(long, value_enum)]
+    import_mode: Option<ImportMode>,
+
+    /// With --import-mode update, delete cards that are no longer in the file
+    #[arg(long)]
+    remove_missing: bool,
+
+    /// Show what an import would do without saving anything
+    #[arg(long, requires = "any_import")]
+    dry_run: bool,
+
+    /// Import from Anki export (.apkg, .colpkg or tab-separated .txt)
     #[arg(short = 'a', long)]
     import_anki: Option<PathBuf>,
 
-    /// Name for Anki text import (ignored for .apkg files)
+    /// Name for Anki text import (ignored for .apkg and .colpkg files)
     #[arg(long)]
     import_anki_name: Option<String>,
 
-    /// Export all decks to Anki .apkg format (preserves scheduling)
+    /// Export all decks (or those picked with --deck and --tag) to Anki .apkg
+    /// format (preserves scheduling)
     #[arg(short = 'A', long)]
     export_anki: Option<PathBuf>,
+
+    /// Import a Markdown deck, or every .md file in a folder as a deck
+    #[arg(long, value_name = "PATH")]
+    import_markdown: Option<PathBuf>,
+
+    /// Import the flashcards in an Obsidian vault or Logseq graph, a deck
+    /// per folder; run again to pick up edits
+    #[arg(long, value_name = "DIR")]
+    import_vault: Option<PathBuf>,
+
+    /// Export all decks (or those picked with --deck and --tag) as Markdown:
+    /// to this file for one deck, or a file per deck in this folder
+    #[arg(long, value_name = "PATH")]
+    export_markdown: Option<PathBuf>,
+
+    /// Only export this deck, by name or ID (repeat for several decks)
+    #[arg(long = "deck", value_name = "NAME", requires = "export")]
+    decks: Vec<String>,
+
+    /// Only export cards with this tag
+    #[arg(long, requires = "export")]
+    tag: Option<String>,
+
+    /// Storage backend to use (overrides the config file)
+    #[arg(long, value_enum)]
+    backend: Option<StorageBackend>,
+
+    /// Copy all JSON deck files into the SQLite backend and switch to it
+    #[arg(long)]
+    migrate_to_sqlite: bool,
+
+    /// Sync with the sync server in config.toml, or through git if none is set
+    #[arg(long)]
+    sync: bool,
+
+    /// Report decks stored in an older format and the migrations they need
+    #[arg(long)]
+    migrate: bool,
+
+    /// With --migrate, rewrite those decks in the current format
+    #[arg(long, requires = "migrate")]
+    apply: bool,
+
+    /// Encrypt the decks with a passphrase, or change it
+    #[arg(long, conflicts_with = "decrypt")]
+    encrypt: bool,
+
+    /// Decrypt the decks and stop asking for a passphrase
+    #[arg(long)]
+    decrypt: bool,
+}
+
+impl Args {
+    /// Whether a command-line action was given, rather than starting the TUI.
+    fn runs_command(&self) -> bool {
+        self.import.is_some()
+            || self.import_folder.is_some()
+            || self.export_backup.is_some()
+            || self.import_backup.is_some()
+            || self.import_anki.is_some()
+            || self.export_anki.is_some()
+            || self.import_markdown.is_some()
+            || self.import_vault.is_some()
+            || self.export_markdown.is_some()
+            || self.migrate_to_sqlite
+            || self.sync
+            || self.migrate
+            || self.encrypt
+            || self.decrypt
+    }
 }
 
+/// Environment variable holding the decks' passphrase, for scripts.
+const PASSPHRASE_VAR: &str = "SRL_PASSPHRASE";
+/// Environment variable holding the new passphrase for --encrypt.
+const NEW_PASSPHRASE_VAR: &str = "SRL_NEW_PASSPHRASE";
+/// Environment variable holding the passphrase of backups encrypted with
+/// --encrypt-backup.
+const BACKUP_PASSPHRASE_VAR: &str = "SRL_BACKUP_PASSPHRASE";
+
 // ══════════════════════════════════════════════════════════════════════════
 // Main Entry Point
 // ══════════════════════════════════════════════════════════════════════════
@@ -75,46 +198,224 @@ struct Args {
 fn main() -> Result<()> {
     let args = Args::parse();
 
+    // Load config
+    let mut config = Config::load().unwrap_or_default();
+
     // Determine decks directory
-    let decks_dir = args.decks_dir.unwrap_or_else(DeckStorage::default_path);
+    let decks_dir = args.decks_dir.clone().unwrap_or_else(DeckStorage::default_path);
+
+    // Handle JSON -> SQLite migration
+    if args.migrate_to_sqlite {
+        let json_storage = DeckStorage::open(decks_dir.clone(), StorageBackend::Json)?;
+        let db_path = decks_dir.join(storage::sqlite::DATABASE_FILE);
+        let target = SqliteRepository::open(&db_path)?;
+        let (copied, skipped) = json_storage.copy_into(&target)?;
+        println!("Migrated {} decks to {}", copied, db_path.display());
+        if skipped > 0 {
+            println!("Skipped {} decks (already in database)", skipped);
+        }
 
-    // Initialize storage
-    let storage = DeckStorage::new(decks_dir)?;
+        config.storage_backend = StorageBackend::Sqlite;
+        config.save()?;
+        println!("Storage backend set to sqlite (JSON files were left in place)");
+        return Ok(());
+    }
+
+    // Initialize storage; the TUI asks for the passphrase itself
+    let backend = args.backend.unwrap_or(config.storage_backend);
+    if !args.runs_command() {
+        return run_tui(decks_dir, backend, config);
+    }
+    let mut storage = open_storage(decks_dir, backend)?;
+
+    // Handle turning encryption on, changing the passphrase, or turning it off
+    if args.encrypt || args.decrypt {
+        let was_encrypted = storage.is_encrypted();
+        let count = if args.encrypt {
+            let passphrase = new_passphrase()?;
+            storage.set_passphrase(Some(&passphrase))?
+        } else {
+            storage.set_passphrase(None)?
+        };
+
+        if args.decrypt {
+            println!("Decrypted {} decks", count);
+        } else {
+            println!("Encrypted {} decks; srl will ask for the passphrase on startup", count);
+            if was_encrypted {
+                println!("Backups made before now still need the old passphrase");
+            } else {
+                println!(
+                    "Backups made before now are not encrypted; delete those in {} if they hold sensitive decks",
+                    BackupRotation::new(&config.backup).dir().display()
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    // Handle git sync
+    if args.sync {
+        let (summary, conflicts) = sync::run(&storage, &config.sync)?;
+        println!("{}", summary);
+        for conflict in &conflicts {
+            println!(
+                "  {}: kept local \"{}\" (remote has \"{}\")",
+                conflict.deck_name, conflict.ours.front, conflict.theirs.front
+            );
+        }
+        if !conflicts.is_empty() {
+            println!("Cards edited on both sides kept the local text; edit them in the card browser to take the remote text");
+        }
+        return Ok(());
+    }
+
+    // Handle deck format migration
+    if args.migrate {
+        let report = storage.schema_report()?;
+        let mut pending = 0;
+        let mut unsupported = 0;
+        for status in &report {
+            match &status.pending {
+                Ok(_) if !status.needs_migration() => {}
+                Ok(steps) => {
+                    pending += 1;
+                    if status.version < storage::schema::CURRENT_VERSION {
+                        println!(
+                            "{}: v{} -> v{}",
+                            status.label,
+                            status.version,
+                            storage::schema::CURRENT_VERSION
+                        );
+                    } else {
+                        println!("{}:", status.label);
+                    }
+                    for step in steps {
+                        println!("  - {}", step);
+                    }
+                    if status.duplicate_card_ids > 0 {
+                        println!(
+                            "  - give {} cards that share an ID with another card their own",
+                            status.duplicate_card_ids
+                        );
+                    }
+                }
+                Err(e) => {
+                    unsupported += 1;
+                    println!("{}: {}", status.label, e);
+                }
+            }
+        }
+
+        if pending == 0 {
+            println!(
+                "{} decks are up to date (format v{})",
+                report.len() - unsupported,
+                storage::schema::CURRENT_VERSION
+            );
+        } else if args.apply {
+            let migrated = storage.apply_migrations()?;
+            println!("Migrated {} decks", migrated);
+        } else {
+            println!("{} decks need migrating; run with --apply to rewrite them", pending);
+        }
+        return Ok(());
+    }
+
+    // Vaults are imported again and again as notes change
+    let import_mode = args.import_mode.unwrap_or(if args.import_vault.is_some() {
+        ImportMode::Update
+    } else {
+        ImportMode::Skip
+    });
+    if args.remove_missing && import_mode != ImportMode::Update {
+        anyhow::bail!("--remove-missing only works with --import-mode update");
+    }
+    let import_options = ImportOptions {
+        mode: import_mode,
+        remove_missing: args.remove_missing,
+        dry_run: args.dry_run,
+    };
+    // Import messages say what would happen on a dry run
+    let (imported_verb, updated_verb) = if args.dry_run {
+        ("Would import", "Would update")
+    } else {
+        ("Imported", "Updated")
+    };
+    if args.dry_run {
+        println!("Dry run: nothing will be saved");
+    }
 
     // Handle single file import
-    if let Some(csv_path) = args.import {
+    if let Some(ref csv_path) = args.import {
         // Check if deck with this name already exists
-        if storage.deck_name_exists(&args.import_name) {
-            println!("Skipped: deck '{}' already exists", args.import_name);
+        if import_mode == ImportMode::Skip && storage.deck_name_exists(&args.import_name) {
+            println!(
+                "Skipped: deck '{}' already exists (use --import-mode update to add to it)",
+                args.import_name
+            );
             return Ok(());
         }
-        let deck = storage.import_csv(&csv_path, &args.import_name)?;
-        storage.save_deck(&deck)?;
-        println!(
-            "Imported {} cards into '{}'",
-            deck.cards.len(),
-            deck.name
-        );
+        let importer = csv_importer(&args)?;
+        let imported = importer
+            .import(csv_path, Some(&args.import_name))
+            .with_context(|| format!("{} import failed", importer.name()))?;
+        for deck in imported.decks {
+            let card_count = deck.cards.len();
+            match formats::save_imported(&storage, deck, &import_options)? {
+                (deck, Saved::Created) => println!("{} {} cards into '{}'", imported_verb, card_count, deck.name),
+                (deck, Saved::Updated(changes)) => println!("{} '{}': {}", updated_verb, deck.name, changes),
+                (deck, Saved::Skipped) => println!("Skipped: deck '{}' already exists", deck.name),
+            }
+        }
+        if !imported.skipped.is_empty() {
+            println!("Skipped {} rows:", imported.skipped.len());
+            for reason in &imported.skipped {
+                println!("  {}", reason);
+            }
+        }
         return Ok(());
     }
 
     // Handle folder import
-    if let Some(folder_path) = args.import_folder {
-        let (imported, skipped) = storage.import_folder(&folder_path)?;
-        if imported.is_empty() && skipped.is_empty() {
-            println!("No CSV files found in {:?}", folder_path);
-        } else {
-            if !imported.is_empty() {
-                println!("Imported {} decks:", imported.len());
-                for (name, count) in &imported {
-                    println!("  {} ({} cards)", name, count);
-                }
+    if let Some(ref folder_path) = args.import_folder {
+        let report = formats::import_folder(
+            &storage,
+            folder_path,
+            &csv_importer(&args)?,
+            "csv",
+            &import_options,
+        )?;
+        print_folder_import(report, folder_path, "CSV", (imported_verb, updated_verb));
+        return Ok(());
+    }
+
+    // Handle Markdown import, of one file or a folder of them
+    if let Some(ref markdown_path) = args.import_markdown {
+        let importer = MarkdownImporter;
+        if markdown_path.is_dir() {
+            let report = formats::import_folder(&storage, markdown_path, &importer, "md", &import_options)?;
+            print_folder_import(report, markdown_path, importer.name(), (imported_verb, updated_verb));
+            return Ok(());
+        }
+        let imported = importer
+            .import(markdown_path, None)
+            .with_context(|| format!("{} import failed", importer.name()))?;
+        for deck in imported.decks {
+            let card_count = deck.cards.len();
+            match formats::save_imported(&storage, deck, &import_options)? {
+                (deck, Saved::Created) => println!("{} {} cards into '{}'", imported_verb, card_count, deck.name),
+                (deck, Saved::Updated(changes)) => println!("{} '{}': {}", updated_verb, deck.name, changes),
+                (deck, Saved::Skipped) => println!(
+                    "Skipped: deck '{}' already exists (use --import-mode update to add to it)",
+                    deck.name
+                ),
             }
-            if !skipped.is_empty() {
-                println!("Skipped {} decks (already exist):", skipped.len());
-                for name in &skipped {
-                    println!("  {}", name);
-                }
+        }
+        if !imported.skipped.is_empty() {
+            println!("Skipped {} parts:", imported.skipped.len());
+            for reason in &imported.skipped {
+                println!("  {}", reason);
             }
         }
         return Ok(());
@@ -122,56 +423,145 @@ fn main() -> Result<()> {
 
     // Handle backup export
     if let Some(backup_path) = args.export_backup {
-        let count = storage.export_backup(&backup_path)?;
-        println!("Exported {} decks to {}", count, backup_path.display());
+        let decks = selected_decks(&storage, &args.decks, args.tag.as_deref())?;
+        let count = if args.encrypt_backup {
+            let passphrase = read_passphrase(BACKUP_PASSPHRASE_VAR, "Backup passphrase: ")?;
+            storage.export_encrypted_backup(&decks, &backup_path, &passphrase)?
+        } else {
+            storage.export_decks_backup(&decks, &backup_path)?
+        };
+        let encrypted = if args.encrypt_backup || storage.is_encrypted() { " (encrypted)" } else { "" };
+        println!("Exported {} decks to {}{}", count, backup_path.display(), encrypted);
         return Ok(());
     }
 
     // Handle Anki export
     if let Some(anki_path) = args.export_anki {
-        let card_count = storage.export_apkg(&anki_path, None)?;
+        let exporter = AnkiPackageExporter::with_media(&storage);
+        let decks = selected_decks(&storage, &args.decks, args.tag.as_deref())?;
+        let card_count = exporter.export(&decks, &anki_path)?;
+        println!(
+            "Exported {} cards to {} ({} format)",
+            card_count,
+            anki_path.display(),
+            exporter.name()
+        );
+        return Ok(());
+    }
+
+    // Handle Obsidian and Logseq vault import
+    if let Some(ref vault_path) = args.import_vault {
+        let importer = VaultImporter;
+        let imported = importer
+            .import(vault_path, None)
+            .with_context(|| format!("{} import failed", importer.name()))?;
+        if imported.decks.is_empty() {
+            println!(
+                "No cards found in {:?} (Obsidian notes need a #flashcards tag, Logseq blocks a #card tag)",
+                vault_path
+            );
+        }
+
+        let mut report = FolderImport::default();
+        for deck in imported.decks {
+            let card_count = deck.cards.len();
+            match formats::save_imported(&storage, deck, &import_options)? {
+                (deck, Saved::Created) => report.imported.push((deck.name, card_count)),
+                (deck, Saved::Updated(changes)) => report.updated.push((deck.name, changes)),
+                (deck, Saved::Skipped) => report.skipped.push(deck.name),
+            }
+        }
+        if !(report.imported.is_empty() && report.updated.is_empty() && report.skipped.is_empty()) {
+            print_folder_import(report, vault_path, importer.name(), (imported_verb, updated_verb));
+        }
+        if !imported.skipped.is_empty() {
+            println!("Skipped {} cards:", imported.skipped.len());
+            for reason in &imported.skipped {
+                println!("  {}", reason);
+            }
+        }
+        return Ok(());
+    }
+
+    // Handle Markdown export
+    if let Some(markdown_path) = args.export_markdown {
+        let decks = selected_decks(&storage, &args.decks, args.tag.as_deref())?;
+        let card_count = MarkdownExporter.export(&decks, &markdown_path)?;
         println!(
-            "Exported {} cards to {} (Anki format)",
+            "Exported {} cards in {} decks to {} ({} format)",
             card_count,
-            anki_path.display()
+            decks.len(),
+            markdown_path.display(),
+            MarkdownExporter.name()
         );
         return Ok(());
     }
 
     // Handle backup import
     if let Some(backup_path) = args.import_backup {
-        let (imported, skipped) = storage.import_backup(&backup_path)?;
-        if skipped > 0 {
-            println!("Imported {} decks ({} skipped - already exist)", imported, skipped);
-        } else {
-            println!("Imported {} decks", imported);
-        }
+        // Backups encrypted with another passphrase than the decks' ask for it
+        let report = match storage.import_backup(&backup_path, args.restore_mode, None, args.dry_run) {
+            Err(e) if e.is::<Locked>() => {
+                let passphrase = read_passphrase(BACKUP_PASSPHRASE_VAR, "Backup passphrase: ")?;
+                storage
+                    .import_backup(&backup_path, args.restore_mode, Some(&passphrase), args.dry_run)
+                    .with_context(|| format!("Failed to decrypt {:?}", backup_path))?
+            }
+            result => result?,
+        };
+        println!("{}", report);
         return Ok(());
     }
 
     // Handle Anki import
     if let Some(anki_path) = args.import_anki {
-        let decks = storage.import_anki(&anki_path, args.import_anki_name.as_deref())?;
+        let importer = AnkiImporter;
+        let package = importer
+            .import_package(&anki_path, args.import_anki_name.as_deref())
+            .with_context(|| format!("{} import failed", importer.name()))?;
         let mut total_cards = 0;
+        let mut total_media = 0;
         let mut saved_count = 0;
         let mut skipped_names = Vec::new();
 
-        for deck in decks {
-            if storage.deck_name_exists(&deck.name) {
-                skipped_names.push(deck.name);
-            } else {
-                total_cards += deck.cards.len();
-                storage.save_deck(&deck)?;
-                println!("  {} ({} cards)", deck.name, deck.cards.len());
-                saved_count += 1;
+        for deck in package.decks {
+            let card_count = deck.cards.len();
+            let deck = match formats::save_imported(&storage, deck, &import_options)? {
+                (deck, Saved::Skipped) => {
+                    skipped_names.push(deck.name);
+                    continue;
+                }
+                (deck, Saved::Created) => {
+                    total_cards += card_count;
+                    saved_count += 1;
+                    println!("  {} ({} cards)", deck.name, card_count);
+                    deck
+                }
+                (deck, Saved::Updated(changes)) => {
+                    println!("  {} (updated: {})", deck.name, changes);
+                    deck
+                }
+            };
+            if args.dry_run {
+                continue;
             }
+            total_media += package
+                .media
+                .save_for(&deck, &storage)
+                .with_context(|| format!("Failed to import the media of deck '{}'", deck.name))?;
         }
 
         if saved_count > 0 {
-            println!("Imported {} deck(s) with {} total cards", saved_count, total_cards);
+            println!("{} {} deck(s) with {} total cards", imported_verb, saved_count, total_cards);
+        }
+        if total_media > 0 {
+            println!("Imported {} media files", total_media);
         }
         if !skipped_names.is_empty() {
-            println!("Skipped {} deck(s) (already exist):", skipped_names.len());
+            println!(
+                "Skipped {} deck(s) (already exist; use --import-mode update to add to them):",
+                skipped_names.len()
+            );
             for name in skipped_names {
                 println!("  {}", name);
             }
@@ -179,11 +569,134 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    // Run TUI
-    run_tui(storage)
+    Ok(())
+}
+
+/// Print what a folder import did, or that it found no `format` files.
+fn print_folder_import(report: FolderImport, folder_path: &std::path::Path, format: &str, verbs: (&str, &str)) {
+    let FolderImport { imported, updated, skipped } = report;
+    let (imported_verb, updated_verb) = verbs;
+    if imported.is_empty() && updated.is_empty() && skipped.is_empty() {
+        println!("No {} files found in {:?}", format, folder_path);
+        return;
+    }
+    if !imported.is_empty() {
+        println!("{} {} decks:", imported_verb, imported.len());
+        for (name, count) in &imported {
+            println!("  {} ({} cards)", name, count);
+        }
+    }
+    if !updated.is_empty() {
+        println!("{} {} decks:", updated_verb, updated.len());
+        for (name, changes) in &updated {
+            println!("  {} ({})", name, changes);
+        }
+    }
+    if !skipped.is_empty() {
+        println!("Skipped {} decks (already exist):", skipped.len());
+        for name in &skipped {
+            println!("  {}", name);
+        }
+    }
+}
+
+/// The CSV importer set up by --csv-delimiter and --csv-columns.
+fn csv_importer(args: &Args) -> Result<CsvImporter> {
+    let mut importer = CsvImporter::default();
+    if let Some(ref delimiter) = args.csv_delimiter {
+        importer = importer.with_delimiter(formats::parse_delimiter(delimiter)?);
+    }
+    if let Some(ref columns) = args.csv_columns {
+        importer = importer.with_columns(CsvColumn::parse_list(columns)?);
+    }
+    Ok(importer)
+}
+
+/// The decks to export: those named in `names` (all when empty), keeping
+/// only the cards tagged `tag` if given.
+fn selected_decks(storage: &DeckStorage, names: &[String], tag: Option<&str>) -> Result<Vec<models::Deck>> {
+    let mut decks = if names.is_empty() {
+        storage.load_decks(None)?
+    } else {
+        let infos = storage.list_decks()?;
+        let ids = names
+            .iter()
+            .map(|name| {
+                infos
+                    .iter()
+                    .find(|d| d.id == *name || d.name.to_lowercase() == name.to_lowercase())
+                    .map(|d| d.id.clone())
+                    .with_context(|| format!("No deck named '{}'", name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        storage.load_decks(Some(&ids))?
+    };
+
+    if let Some(tag) = tag {
+        for deck in &mut decks {
+            deck.cards.retain(|card| card.has_tag(tag));
+        }
+        decks.retain(|deck| !deck.cards.is_empty());
+        if decks.is_empty() {
+            anyhow::bail!("No cards tagged '{}'", tag);
+        }
+    }
+    Ok(decks)
+}
+
+/// Read a passphrase from the environment variable `var`, or else prompt for it.
+fn read_passphrase(var: &str, prompt: &str) -> Result<Zeroizing<String>> {
+    if let Ok(passphrase) = std::env::var(var) {
+        return Ok(Zeroizing::new(passphrase));
+    }
+    let passphrase = rpassword::prompt_password(prompt)
+        .with_context(|| format!("Failed to read passphrase (without a terminal, set {})", var))?;
+    Ok(Zeroizing::new(passphrase))
 }
 
-fn run_tui(storage: DeckStorage) -> Result<()> {
+/// Prompt for a new passphrase twice, unless it is in the environment.
+fn new_passphrase() -> Result<Zeroizing<String>> {
+    if let Ok(passphrase) = std::env::var(NEW_PASSPHRASE_VAR) {
+        return Ok(Zeroizing::new(passphrase));
+    }
+    let passphrase = read_passphrase(NEW_PASSPHRASE_VAR, "New passphrase: ")?;
+    let repeated = read_passphrase(NEW_PASSPHRASE_VAR, "Repeat new passphrase: ")?;
+    if passphrase != repeated {
+        anyhow::bail!("The passphrases don't match");
+    }
+    Ok(passphrase)
+}
+
+/// Open the decks for a command-line action, asking for the passphrase if
+/// they are encrypted.
+fn open_storage(decks_dir: PathBuf, backend: StorageBackend) -> Result<DeckStorage> {
+    if !DeckStorage::needs_passphrase(&decks_dir, backend) {
+        return DeckStorage::open(decks_dir, backend);
+    }
+    let passphrase = read_passphrase(PASSPHRASE_VAR, "Passphrase: ")?;
+    let cipher = storage::Cipher::unlock(&decks_dir, &passphrase)?;
+    DeckStorage::open_encrypted(decks_dir, backend, cipher)
+}
+
+/// Open the decks for the TUI, showing the unlock screen if they are
+/// encrypted. Returns `None` if the user quits there.
+fn open_tui_storage<B: Backend>(
+    terminal: &mut Terminal<B>,
+    decks_dir: PathBuf,
+    backend: StorageBackend,
+    config: &Config,
+) -> Result<Option<DeckStorage>> {
+    if !DeckStorage::needs_passphrase(&decks_dir, backend) {
+        return DeckStorage::open(decks_dir, backend).map(Some);
+    }
+    let theme = Theme::from_name(&config.theme);
+    match ui::prompt_passphrase(terminal, &decks_dir, &theme)? {
+        Some(cipher) => DeckStorage::open_encrypted(decks_dir, backend, cipher).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn run_tui(decks_dir: PathBuf, storage_backend: StorageBackend, config: Config) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -191,23 +704,36 @@ fn run_tui(storage: DeckStorage) -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Load config
-    let config = config::Config::load().unwrap_or_default();
+    let storage = match open_tui_storage(&mut terminal, decks_dir, storage_backend, &config) {
+        Ok(Some(storage)) => storage,
+        result => {
+            restore_terminal(&mut terminal)?;
+            return result.map(|_| ());
+        }
+    };
+
+    // In-memory decks have nothing worth backing up
+    let auto_backup = storage_backend != StorageBackend::Memory;
+    let backups = BackupRotation::new(&config.backup);
+    let daily_backup = (auto_backup && config.backup.daily && backups.daily_due())
+        .then(|| backups.create(&storage));
 
     // Create app
     let mut app = App::new(storage, config);
+    if let Some(Err(e)) = daily_backup {
+        app.set_status(format!("Daily backup failed: {:#}", e));
+    }
 
     // Run main loop
     let result = run_app(&mut terminal, &mut app);
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    restore_terminal(&mut terminal)?;
+
+    if auto_backup && app.config.backup.on_exit {
+        if let Err(e) = backups.create(&app.storage) {
+            eprintln!("Warning: backup on exit failed: {:#}", e);
+        }
+    }
 
     // Handle any errors
     if let Err(err) = result {
@@ -218,10 +744,52 @@ fn run_tui(storage: DeckStorage) -> Result<()> {
     Ok(())
 }
 
+fn restore_terminal<B: Backend + io::Write>(terminal: &mut Terminal<B>) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
     while app.running {
         terminal.draw(|frame| app.render(frame))?;
         app.handle_events()?;
+        app.poll_external_changes();
+
+        if let Some(path) = app.pending_edit.take() {
+            match open_in_editor(&path) {
+                Ok(()) => app.set_status(format!("Rescanned after editing {}", path.display())),
+                Err(e) => app.set_status(format!("Could not open editor: {}", e)),
+            }
+            terminal.clear()?;
+            app.refresh_deck_list();
+        }
+    }
+    Ok(())
+}
+
+/// Suspend the TUI and open a file in the user's editor ($VISUAL, $EDITOR, or vi).
+fn open_in_editor(path: &std::path::Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+
+    let status = std::process::Command::new(&editor).arg(path).status();
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+
+    let status = status.with_context(|| format!("failed to run '{}'", editor))?;
+    if !status.success() {
+        anyhow::bail!("'{}' exited with {}", editor, status);
     }
     Ok(())
 }
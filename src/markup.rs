@@ -0,0 +1,409 @@
+//! Lightweight markup for card text.
+//!
+//! Cards are plain text with a small Markdown subset, which the TUI renders
+//! and Anki import/export converts from and to HTML:
+//!
+//! ```text
+//! **bold**  *italic*  `code`  \* (a literal asterisk)
+//! - bullet item
+//! 1. numbered item
+//! ```
+//! with fenced code blocks between lines of three backticks, and media
+//! references as described in `models::media_refs`. Line breaks are kept
+//! as they are.
+
+use crate::models::{media_refs, MediaKind};
+
+/// Opens and closes a fenced code block.
+pub const FENCE: &str = "```";
+
+/// A run of text in one style, with the markup removed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Run {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+}
+
+/// Whether a line opens or closes a fenced code block.
+pub fn is_fence(line: &str) -> bool {
+    line.trim_start().starts_with(FENCE)
+}
+
+/// A list item's marker (`- `, `* ` or `1. `) and the rest of the line,
+/// after any indentation.
+pub fn list_item(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    if let Some(rest) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+        return Some((&trimmed[..2], rest));
+    }
+    let digits = trimmed.find(|c: char| !c.is_ascii_digit())?;
+    let rest = trimmed[digits..].strip_prefix(". ")?;
+    (digits > 0).then(|| (&trimmed[..digits + 2], rest))
+}
+
+/// Split one line of card text into styled runs. Markers without a partner
+/// later on the line are kept as text.
+pub fn runs(line: &str) -> Vec<Run> {
+    let mut runs = Vec::new();
+    let mut current = Run::default();
+    let mut rest = line;
+
+    fn flush(runs: &mut Vec<Run>, current: &mut Run) {
+        if !current.text.is_empty() {
+            runs.push(current.clone());
+            current.text.clear();
+        }
+    }
+
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+        if current.code {
+            // Code is literal up to the closing backtick
+            if c == '`' {
+                flush(&mut runs, &mut current);
+                current.code = false;
+            } else {
+                current.text.push(c);
+            }
+            rest = after;
+            continue;
+        }
+
+        match c {
+            '\\' if after.starts_with(['\\', '*', '`']) => {
+                current.text.push_str(&after[..1]);
+                rest = &after[1..];
+                continue;
+            }
+            '`' if after.contains('`') => {
+                flush(&mut runs, &mut current);
+                current.code = true;
+            }
+            '*' if after.starts_with('*') && (current.bold || opens(&after[1..]) && after[1..].contains("**")) => {
+                flush(&mut runs, &mut current);
+                current.bold = !current.bold;
+                rest = &after[1..];
+                continue;
+            }
+            '*' if current.italic || opens(after) && has_closing_star(after) => {
+                flush(&mut runs, &mut current);
+                current.italic = !current.italic;
+            }
+            _ => current.text.push(c),
+        }
+        rest = after;
+    }
+    flush(&mut runs, &mut current);
+    runs
+}
+
+/// Whether a marker followed by `text` can open a run: as in Markdown, not
+/// when followed by a space, so "2 * 3 * 4" stays as it is.
+fn opens(text: &str) -> bool {
+    text.starts_with(|c: char| !c.is_whitespace())
+}
+
+/// Whether a single `*` follows that can close an italic run.
+fn has_closing_star(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    (0..bytes.len()).any(|i| {
+        bytes[i] == b'*'
+            && bytes.get(i + 1) != Some(&b'*')
+            && (i == 0 || bytes[i - 1] != b'*' && bytes[i - 1] != b'\\')
+    })
+}
+
+/// Escape characters that would otherwise read as markup.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// ══════════════════════════════════════════════════════════════════════════
+// HTML to markup
+// ══════════════════════════════════════════════════════════════════════════
+
+/// Convert an Anki field's HTML to card text. Formatting without a markup
+/// equivalent (colors, underline, tables) is dropped, keeping its text.
+pub fn from_html(html: &str) -> String {
+    let mut out = String::new();
+    // Open lists, with the next number for ordered ones
+    let mut lists: Vec<Option<u32>> = Vec::new();
+    let mut in_pre = false;
+    let mut in_code = false;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(start) = find_tag(rest) else {
+            push_text(&mut out, rest, in_pre || in_code);
+            break;
+        };
+        push_text(&mut out, &rest[..start], in_pre || in_code);
+        let Some(len) = rest[start..].find('>') else {
+            // Not a tag after all
+            push_text(&mut out, &rest[start..], in_pre || in_code);
+            break;
+        };
+        let tag = &rest[start + 1..start + len];
+        rest = &rest[start + len + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        match (name.as_str(), closing) {
+            ("br", _) => out.push('\n'),
+            ("div" | "p" | "tr", _) => end_line(&mut out),
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                end_line(&mut out);
+                out.push_str("**");
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => {
+                out.push_str("**");
+                end_line(&mut out);
+            }
+            ("b" | "strong", _) if !in_pre && !in_code => out.push_str("**"),
+            ("i" | "em", _) if !in_pre && !in_code => out.push('*'),
+            ("code" | "kbd" | "tt", _) if !in_pre && in_code == closing => {
+                out.push('`');
+                in_code = !closing;
+            }
+            ("pre", _) if in_pre == closing => {
+                end_line(&mut out);
+                out.push_str(FENCE);
+                out.push('\n');
+                in_pre = !closing;
+            }
+            ("ul", false) => lists.push(None),
+            ("ol", false) => lists.push(Some(1)),
+            ("ul" | "ol", true) => {
+                lists.pop();
+                end_line(&mut out);
+            }
+            ("li", false) => {
+                end_line(&mut out);
+                out.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        out.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => out.push_str("- "),
+                }
+            }
+            ("img", false) => {
+                if let Some(src) = img_src(tag) {
+                    out.push_str(&format!("![]({})", decode_entities(src)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Block tags leave blank lines around and trailing spaces behind
+    let lines: Vec<&str> = out.lines().map(str::trim_end).collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// Byte offset of the next tag. A `<` not followed by a tag name, as in
+/// "a < b", is text.
+fn find_tag(html: &str) -> Option<usize> {
+    html.match_indices('<').map(|(i, _)| i).find(|&i| {
+        html[i + 1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!')
+    })
+}
+
+fn push_text(out: &mut String, text: &str, literal: bool) {
+    let text = decode_entities(text);
+    if literal {
+        out.push_str(&text);
+    } else {
+        out.push_str(&escape(&text));
+    }
+}
+
+/// Start a new line unless at the start of one.
+fn end_line(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// The `src` of an `<img>` tag, given the text between `<` and `>`.
+fn img_src(tag: &str) -> Option<&str> {
+    let attrs = tag.get(3..).filter(|_| tag[..3].eq_ignore_ascii_case("img"))?;
+    if !attrs.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let value = &attrs[attrs.find("src=")? + "src=".len()..];
+    let src = match value.chars().next()? {
+        quote @ ('"' | '\'') => value[1..].split(quote).next()?,
+        _ => value.split(|c: char| c.is_whitespace() || c == '/').next()?,
+    };
+    (!src.is_empty()).then_some(src)
+}
+
+/// Decode named and numeric HTML entities.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "nbsp" => Some(' '),
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// ══════════════════════════════════════════════════════════════════════════
+// Markup to HTML
+// ══════════════════════════════════════════════════════════════════════════
+
+/// Convert card text to HTML for an Anki field.
+pub fn to_html(text: &str) -> String {
+    let mut html = String::new();
+    // Indentation of each open list, and whether it is numbered
+    let mut lists: Vec<(usize, bool)> = Vec::new();
+    let mut in_pre = false;
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if is_fence(line) {
+            close_lists(&mut html, &mut lists, 0);
+            html.push_str(if in_pre { "</pre>" } else { "<pre>" });
+            in_pre = !in_pre;
+            continue;
+        }
+        if in_pre {
+            html.push_str(&escape_html(line));
+            if lines.peek().is_some_and(|next| !is_fence(next)) {
+                html.push('\n');
+            }
+            continue;
+        }
+
+        if let Some((marker, item)) = list_item(line) {
+            let indent = line.len() - line.trim_start().len();
+            let numbered = marker.as_bytes()[0].is_ascii_digit();
+            close_lists(&mut html, &mut lists, indent + 1);
+            if lists.last().is_none_or(|&(open, _)| open < indent) {
+                html.push_str(if numbered { "<ol>" } else { "<ul>" });
+                lists.push((indent, numbered));
+            }
+            html.push_str("<li>");
+            html.push_str(&inline_html(item));
+            html.push_str("</li>");
+            continue;
+        }
+
+        let had_list = !lists.is_empty();
+        close_lists(&mut html, &mut lists, 0);
+        if !html.is_empty() && !had_list && !html.ends_with("</pre>") {
+            html.push_str("<br>");
+        }
+        html.push_str(&inline_html(line));
+    }
+
+    close_lists(&mut html, &mut lists, 0);
+    if in_pre {
+        html.push_str("</pre>");
+    }
+    html
+}
+
+/// Close the lists indented by `indent` spaces or more; 0 closes them all.
+fn close_lists(html: &mut String, lists: &mut Vec<(usize, bool)>, indent: usize) {
+    while let Some(&(open, numbered)) = lists.last() {
+        if open < indent {
+            break;
+        }
+        html.push_str(if numbered { "</ol>" } else { "</ul>" });
+        lists.pop();
+    }
+}
+
+/// HTML for one line's inline markup and media references.
+fn inline_html(line: &str) -> String {
+    let mut html = String::new();
+    for run in runs(line) {
+        let mut text = escape_html(&run.text);
+        if !run.code {
+            text = images_to_html(&text);
+        }
+        let (open, close) = match (run.code, run.bold, run.italic) {
+            (true, _, _) => ("<code>", "</code>"),
+            (false, true, true) => ("<b><i>", "</i></b>"),
+            (false, true, false) => ("<b>", "</b>"),
+            (false, false, true) => ("<i>", "</i>"),
+            (false, false, false) => ("", ""),
+        };
+        html.push_str(open);
+        html.push_str(&text);
+        html.push_str(close);
+    }
+    html
+}
+
+/// Turn image references into `<img>` tags; `[sound:]` references are the
+/// same in Anki's HTML.
+fn images_to_html(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    let mut pos = 0;
+    for media in media_refs(text) {
+        if media.kind == MediaKind::Image {
+            html.push_str(&text[pos..media.range.start]);
+            html.push_str(&format!("<img src=\"{}\">", media.name.replace('"', "&quot;")));
+            pos = media.range.end;
+        }
+    }
+    html.push_str(&text[pos..]);
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -2,6 +2,7 @@
 
 use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use uuid::Uuid;
 
 /// Rating for how well you remembered a card.
@@ -54,7 +55,7 @@ impl ReviewRating {
 }
 
 /// A single flashcard.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Card {
     pub id: String,
     pub front: String,
@@ -79,12 +80,33 @@ pub struct Card {
     #[serde(default)]
     pub notes: String,
     pub created_at: DateTime<Local>,
+
+    /// Every review of this card, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reviews: Vec<Review>,
+
+    /// Left out of study sessions until unsuspended.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suspended: bool,
+    /// Left out of study sessions until then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buried_until: Option<DateTime<Local>>,
+}
+
+/// One entry in a card's review log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Review {
+    pub reviewed_at: DateTime<Local>,
+    pub rating: ReviewRating,
+    /// Interval in days and ease factor the review scheduled.
+    pub interval: u32,
+    pub ease_factor: f64,
 }
 
 impl Card {
     pub fn new(front: String, back: String) -> Self {
         Self {
-            id: Uuid::new_v4().to_string()[..8].to_string(),
+            id: new_id(),
             front,
             back,
             ease_factor: 2.5,
@@ -97,6 +119,9 @@ impl Card {
             tags: Vec::new(),
             notes: String::new(),
             created_at: Local::now(),
+            reviews: Vec::new(),
+            suspended: false,
+            buried_until: None,
         }
     }
 
@@ -104,6 +129,27 @@ impl Card {
         self.repetitions == 0
     }
 
+    /// Whether the card is suspended or buried, keeping it out of study sessions.
+    pub fn is_held(&self) -> bool {
+        self.suspended || self.buried_until.is_some_and(|until| Local::now() < until)
+    }
+
+    /// Names of the media files the card refers to, front first.
+    pub fn media_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for media in media_refs(&self.front).into_iter().chain(media_refs(&self.back)) {
+            if !names.contains(&media.name) {
+                names.push(media.name);
+            }
+        }
+        names
+    }
+
+    /// Whether the card has a tag, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase())
+    }
+
     pub fn is_due(&self) -> bool {
         match self.due_date {
             None => true,
@@ -134,7 +180,7 @@ pub struct DeckStats {
 }
 
 /// A collection of flashcards.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Deck {
     pub id: String,
     pub name: String,
@@ -146,10 +192,16 @@ pub struct Deck {
     pub last_studied: Option<DateTime<Local>>,
 }
 
+/// Generate an ID for a new deck or card: a full random UUID, in hex.
+/// Older decks have 8-character IDs, which keep working as they are.
+pub fn new_id() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
 impl Deck {
     pub fn new(name: String) -> Self {
         Self {
-            id: Uuid::new_v4().to_string()[..8].to_string(),
+            id: new_id(),
             name,
             description: String::new(),
             cards: Vec::new(),
@@ -164,6 +216,33 @@ impl Deck {
         self.cards.last().unwrap()
     }
 
+    /// Give each card sharing its ID with an earlier card of the deck an ID
+    /// of its own, `<id>-2`, `<id>-3`, and so on, so loading the same deck
+    /// twice gives the same IDs. Returns the number of cards renamed.
+    pub fn dedupe_card_ids(&mut self) -> usize {
+        let mut taken: HashSet<String> = self.cards.iter().map(|c| c.id.clone()).collect();
+        if taken.len() == self.cards.len() {
+            return 0;
+        }
+
+        let mut seen = HashSet::new();
+        let mut renamed = 0;
+        for card in &mut self.cards {
+            if seen.insert(card.id.clone()) {
+                continue;
+            }
+            let id = (2..)
+                .map(|n| format!("{}-{}", card.id, n))
+                .find(|id| !taken.contains(id))
+                .expect("some suffix is free");
+            taken.insert(id.clone());
+            seen.insert(id.clone());
+            card.id = id;
+            renamed += 1;
+        }
+        renamed
+    }
+
     pub fn get_due_cards(&self) -> Vec<&Card> {
         self.cards.iter().filter(|c| c.is_due()).collect()
     }
@@ -179,7 +258,9 @@ impl Deck {
         };
 
         for card in &self.cards {
-            if card.is_new() {
+            if card.is_held() {
+                // Not studied, so neither new nor due
+            } else if card.is_new() {
                 stats.new_cards += 1;
             } else if card.is_due() {
                 stats.due_cards += 1;
@@ -213,3 +294,62 @@ impl Deck {
         self.cards.len() < len_before
     }
 }
+
+/// What a media reference in card text points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Image,
+    Sound,
+}
+
+/// A media file referenced from card text: `![alt](name)` for images, and
+/// Anki's `[sound:name]` for audio. Names are files in the deck's media
+/// folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaRef<'a> {
+    pub kind: MediaKind,
+    pub name: &'a str,
+    /// Byte range of the whole reference within the text.
+    pub range: std::ops::Range<usize>,
+}
+
+/// Find the media references in card text, in order.
+pub fn media_refs(text: &str) -> Vec<MediaRef<'_>> {
+    let mut refs = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = text[pos..].find(['!', '[']) {
+        let start = pos + offset;
+        // References don't span lines
+        let rest = &text[start..];
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+
+        let found = if let Some(inner) = line.strip_prefix("[sound:") {
+            inner
+                .find(']')
+                .map(|end| (MediaKind::Sound, &inner[..end], "[sound:".len() + end + 1))
+        } else if line.starts_with("![") {
+            line.find("](").and_then(|mid| {
+                let inner = &line[mid + 2..];
+                inner
+                    .find(')')
+                    .map(|end| (MediaKind::Image, &inner[..end], mid + 2 + end + 1))
+            })
+        } else {
+            None
+        };
+
+        match found {
+            Some((kind, name, len)) if !name.trim().is_empty() => {
+                refs.push(MediaRef {
+                    kind,
+                    name: name.trim(),
+                    range: start..start + len,
+                });
+                pos = start + len;
+            }
+            _ => pos = start + 1,
+        }
+    }
+    refs
+}
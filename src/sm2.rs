@@ -4,7 +4,7 @@
 
 use chrono::{Duration, Local};
 
-use crate::models::{Card, ReviewRating};
+use crate::models::{Card, Review, ReviewRating};
 
 /// Result of reviewing a card.
 #[derive(Debug)]
@@ -109,6 +109,12 @@ impl Scheduler {
         card.due_date = Some(next_due);
         card.last_reviewed = Some(now);
         card.total_reviews += 1;
+        card.reviews.push(Review {
+            reviewed_at: now,
+            rating,
+            interval: new_interval,
+            ease_factor: new_ease_factor,
+        });
 
         ReviewResult {
             new_interval,
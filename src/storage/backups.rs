@@ -0,0 +1,178 @@
+//! Automatic backups with a daily/weekly retention policy.
+//!
+//! Automatic backups are ordinary backup files named `auto_<timestamp>.json`.
+//! Only files with that name are ever pruned, so manual backups saved to the
+//! same directory are left alone.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone};
+use serde::de::IgnoredAny;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::crypto;
+use super::DeckStorage;
+use crate::config::BackupConfig;
+
+const PREFIX: &str = "auto_";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// An automatic backup file.
+#[derive(Debug, Clone)]
+pub struct BackupEntry {
+    pub path: PathBuf,
+    pub created_at: DateTime<Local>,
+    pub size: u64,
+    /// Number of decks, or `None` if the file couldn't be read.
+    pub deck_count: Option<usize>,
+    /// Whether the file is encrypted. Encrypted backups aren't opened just
+    /// to count their decks.
+    pub encrypted: bool,
+}
+
+/// Just enough of a backup file to count its decks.
+#[derive(serde::Deserialize)]
+struct BackupSummary {
+    decks: Vec<IgnoredAny>,
+}
+
+/// Creates, lists, and prunes automatic backups.
+pub struct BackupRotation {
+    dir: PathBuf,
+    keep_daily: usize,
+    keep_weekly: usize,
+}
+
+impl BackupRotation {
+    pub fn new(config: &BackupConfig) -> Self {
+        Self {
+            dir: config.dir.clone().unwrap_or_else(Self::default_dir),
+            keep_daily: config.keep_daily,
+            keep_weekly: config.keep_weekly,
+        }
+    }
+
+    /// Default directory for automatic backups.
+    pub fn default_dir() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("flashcards")
+            .join("backups")
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Automatic backups, newest first, with their decks counted. Every file
+    /// is read; `daily_due` and `prune` only need the names.
+    pub fn list(&self) -> Result<Vec<BackupEntry>> {
+        let mut backups = Vec::new();
+        for (path, created_at) in self.files()? {
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let data = fs::read(&path).unwrap_or_default();
+            let encrypted = crypto::is_encrypted(&data);
+            let deck_count = serde_json::from_slice::<BackupSummary>(&data)
+                .ok()
+                .map(|summary| summary.decks.len());
+
+            backups.push(BackupEntry {
+                path,
+                created_at,
+                size,
+                deck_count,
+                encrypted,
+            });
+        }
+        Ok(backups)
+    }
+
+    /// Automatic backup files and when they were made, by file name, newest
+    /// first.
+    fn files(&self) -> Result<Vec<(PathBuf, DateTime<Local>)>> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if let Some(created_at) = Self::parse_timestamp(&path) {
+                files.push((path, created_at));
+            }
+        }
+        files.sort_by_key(|(_, created_at)| std::cmp::Reverse(*created_at));
+        Ok(files)
+    }
+
+    /// Whether the newest automatic backup is more than a day old.
+    pub fn daily_due(&self) -> bool {
+        let newest = self.files().ok().and_then(|f| f.first().map(|(_, at)| *at));
+        newest.is_none_or(|at| Local::now() - at >= chrono::Duration::days(1))
+    }
+
+    /// Back up every deck, then prune old backups.
+    /// Returns the new backup's path.
+    pub fn create(&self, storage: &DeckStorage) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create backup directory: {:?}", self.dir))?;
+
+        let name = format!("{}{}.json", PREFIX, Local::now().format(TIMESTAMP_FORMAT));
+        let path = self.dir.join(name);
+        storage
+            .export_backup(&path)
+            .with_context(|| format!("Failed to write backup {:?}", path))?;
+
+        self.prune()?;
+        Ok(path)
+    }
+
+    /// Delete automatic backups outside the retention policy: the newest
+    /// backup of each of the last `keep_daily` days and `keep_weekly` weeks
+    /// that have one. The newest backup is always kept.
+    /// Returns the number of files deleted.
+    pub fn prune(&self) -> Result<usize> {
+        let backups = self.files()?;
+
+        let mut keep: HashSet<&Path> = HashSet::new();
+        let mut days = Vec::new();
+        let mut weeks = Vec::new();
+        for (path, created_at) in &backups {
+            let day = created_at.date_naive();
+            if days.len() < self.keep_daily && !days.contains(&day) {
+                days.push(day);
+                keep.insert(path);
+            }
+            let week = day.iso_week();
+            if weeks.len() < self.keep_weekly && !weeks.contains(&week) {
+                weeks.push(week);
+                keep.insert(path);
+            }
+        }
+        if let Some((newest, _)) = backups.first() {
+            keep.insert(newest);
+        }
+
+        let mut deleted = 0;
+        for (path, _) in &backups {
+            if !keep.contains(path.as_path()) {
+                fs::remove_file(path)
+                    .with_context(|| format!("Failed to delete old backup {:?}", path))?;
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Creation time encoded in an automatic backup's file name.
+    fn parse_timestamp(path: &Path) -> Option<DateTime<Local>> {
+        let stem = path
+            .file_name()?
+            .to_str()?
+            .strip_prefix(PREFIX)?
+            .strip_suffix(".json")?;
+        let naive = NaiveDateTime::parse_from_str(stem, TIMESTAMP_FORMAT).ok()?;
+        Local.from_local_datetime(&naive).earliest()
+    }
+}
@@ -0,0 +1,55 @@
+//! Protection against two srl processes overwriting each other's changes.
+//!
+//! Saves take an advisory lock on a file in the decks directory, and
+//! `DeckStorage` checks the stored deck's version (see
+//! `DeckRepository::version`) is the one it loaded before writing. If
+//! another process changed the deck in between, the save fails with
+//! `DeckConflict` and the caller decides whether to reload, merge (with
+//! `sync::merge_decks`, as syncs do), or overwrite.
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs::{self, File};
+use std::path::Path;
+
+/// Lock file name inside the decks directory.
+pub const LOCK_FILE: &str = ".srl.lock";
+
+/// Exclusive advisory lock on the decks directory, released on drop.
+pub struct DirLock {
+    _file: File,
+}
+
+impl DirLock {
+    /// Block until no other srl process is writing to `dir`.
+    pub fn acquire(dir: &Path) -> Result<Self> {
+        let path = dir.join(LOCK_FILE);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file: {:?}", path))?;
+        file.lock()
+            .with_context(|| format!("Failed to lock {:?}", path))?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// A deck changed on disk since it was loaded.
+#[derive(Debug, Clone)]
+pub struct DeckConflict {
+    pub deck_id: String,
+    pub deck_name: String,
+    /// The stored deck was deleted rather than modified.
+    pub deleted: bool,
+}
+
+impl fmt::Display for DeckConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let change = if self.deleted { "deleted" } else { "modified" };
+        write!(f, "Deck '{}' was {} by another process since it was loaded", self.deck_name, change)
+    }
+}
+
+impl std::error::Error for DeckConflict {}
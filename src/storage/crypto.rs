@@ -0,0 +1,220 @@
+//! Optional passphrase encryption of the files srl writes.
+//!
+//! An encrypted decks directory contains `KEY_FILE`, an encrypted known value
+//! used to check the passphrase. Deck files, media files, the deck index, the
+//! sync state and backups are then written as:
+//!
+//! ```text
+//! MAGIC (8 bytes) | salt (16) | nonce (24) | XChaCha20-Poly1305 ciphertext
+//! ```
+//!
+//! The key is derived from the passphrase and salt with Argon2id. Every file
+//! carries its salt, so any file (a backup copied elsewhere, say) can be
+//! decrypted with just the passphrase. Files in one directory share the salt
+//! of its `KEY_FILE`, so the slow key derivation runs once per session.
+//!
+//! Reading a plaintext file with a cipher set is allowed, so decks added by
+//! hand still load; they are encrypted the next time they are saved.
+
+use anyhow::{Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use zeroize::Zeroizing;
+
+/// Passphrase check file inside an encrypted decks directory.
+pub const KEY_FILE: &str = ".srl-key";
+
+const MAGIC: &[u8; 8] = b"SRLENC\x00\x01";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+/// Plaintext of `KEY_FILE`.
+const KEY_CHECK: &[u8] = b"srl passphrase check";
+
+type Key = Zeroizing<[u8; 32]>;
+
+/// Whether file contents are encrypted.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Whether a decks directory is encrypted.
+pub fn dir_is_encrypted(dir: &Path) -> bool {
+    dir.join(KEY_FILE).exists()
+}
+
+/// An encrypted file that couldn't be decrypted: no passphrase was given, or
+/// it was encrypted with a different one.
+#[derive(Debug, Clone)]
+pub struct Locked {
+    pub path: PathBuf,
+}
+
+impl fmt::Display for Locked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is encrypted and the passphrase doesn't match", self.path)
+    }
+}
+
+impl std::error::Error for Locked {}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| anyhow::anyhow!("Failed to derive encryption key: {}", e))?;
+    Ok(key)
+}
+
+/// Encrypts and decrypts with keys derived from one passphrase.
+pub struct Cipher {
+    passphrase: Zeroizing<String>,
+    /// Salt new files are encrypted under.
+    salt: [u8; SALT_LEN],
+    /// Keys derived so far, by salt.
+    keys: RefCell<HashMap<[u8; SALT_LEN], Key>>,
+}
+
+impl Cipher {
+    /// A cipher with a fresh salt, for encrypting a directory or a backup.
+    pub fn new(passphrase: &str) -> Result<Self> {
+        if passphrase.is_empty() {
+            anyhow::bail!("The passphrase can't be empty");
+        }
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Ok(Self {
+            passphrase: Zeroizing::new(passphrase.to_string()),
+            salt,
+            keys: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Check `passphrase` against an encrypted directory's `KEY_FILE`.
+    pub fn unlock(dir: &Path, passphrase: &str) -> Result<Self> {
+        let path = dir.join(KEY_FILE);
+        let data = fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        let mut cipher = Self::new(passphrase)?;
+        cipher.salt = Self::salt_of(&data).with_context(|| format!("{:?} is not an srl key file", path))?;
+
+        match cipher.decrypt(&data) {
+            Ok(check) if check == KEY_CHECK => Ok(cipher),
+            _ => anyhow::bail!("Wrong passphrase"),
+        }
+    }
+
+    /// Write the passphrase check file that marks `dir` as encrypted.
+    pub fn write_key_file(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(KEY_FILE);
+        fs::write(&path, self.encrypt(KEY_CHECK)?).with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    fn salt_of(data: &[u8]) -> Option<[u8; SALT_LEN]> {
+        if !is_encrypted(data) || data.len() < HEADER_LEN {
+            return None;
+        }
+        data[MAGIC.len()..MAGIC.len() + SALT_LEN].try_into().ok()
+    }
+
+    fn aead(&self, salt: [u8; SALT_LEN]) -> Result<XChaCha20Poly1305> {
+        let mut keys = self.keys.borrow_mut();
+        let key = match keys.get(&salt) {
+            Some(key) => key,
+            None => keys.entry(salt).or_insert(derive_key(&self.passphrase, &salt)?),
+        };
+        Ok(XChaCha20Poly1305::new(key.as_ref().into()))
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self
+            .aead(self.salt)?
+            .encrypt(XNonce::from_slice(&nonce), plaintext)
+            .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+        let mut data = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&self.salt);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        Ok(data)
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let salt = Self::salt_of(data).context("Not an encrypted srl file")?;
+        let nonce = XNonce::from_slice(&data[MAGIC.len() + SALT_LEN..HEADER_LEN]);
+        self.aead(salt)?
+            .decrypt(nonce, &data[HEADER_LEN..])
+            .map_err(|_| anyhow::anyhow!("Wrong passphrase or damaged file"))
+    }
+}
+
+/// Reads and writes srl's files, encrypting them when a cipher is set.
+#[derive(Clone, Default)]
+pub struct FileCodec {
+    cipher: Option<Rc<Cipher>>,
+}
+
+impl FileCodec {
+    pub fn plain() -> Self {
+        Self::default()
+    }
+
+    pub fn encrypted(cipher: Cipher) -> Self {
+        Self {
+            cipher: Some(Rc::new(cipher)),
+        }
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    pub fn cipher(&self) -> Option<&Cipher> {
+        self.cipher.as_deref()
+    }
+
+    /// Read a file as text, decrypting it if it is encrypted.
+    /// Fails with `Locked` if it can't be decrypted.
+    pub fn read(&self, path: &Path) -> Result<String> {
+        let data = self.read_bytes(path)?;
+        String::from_utf8(data).with_context(|| format!("{:?} is not valid UTF-8", path))
+    }
+
+    /// Read a file, decrypting it if it is encrypted.
+    /// Fails with `Locked` if it can't be decrypted.
+    pub fn read_bytes(&self, path: &Path) -> Result<Vec<u8>> {
+        let data = fs::read(path)?;
+        if !is_encrypted(&data) {
+            return Ok(data);
+        }
+
+        let locked = || Locked { path: path.to_path_buf() };
+        let cipher = self.cipher.as_ref().ok_or_else(locked)?;
+        cipher.decrypt(&data).map_err(|_| locked().into())
+    }
+
+    /// Write text to a file, encrypted if a cipher is set.
+    pub fn write(&self, path: &Path, contents: &str) -> Result<()> {
+        self.write_bytes(path, contents.as_bytes())
+    }
+
+    /// Write a file, encrypted if a cipher is set.
+    pub fn write_bytes(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        match self.cipher {
+            Some(ref cipher) => fs::write(path, cipher.encrypt(contents)?)?,
+            None => fs::write(path, contents)?,
+        }
+        Ok(())
+    }
+}
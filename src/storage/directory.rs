@@ -0,0 +1,249 @@
+//! Directory backend: one directory per deck, one JSON file per card.
+//!
+//! ```text
+//! <decks_dir>/<deck_id>/deck.json          deck metadata and card order
+//! <decks_dir>/<deck_id>/cards/<card>.json  one file per card
+//! ```
+//!
+//! Reviewing a card rewrites a single small file, and diffs stay readable when
+//! the decks directory is kept in version control.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::crypto::FileCodec;
+use super::schema::{self, SchemaStatus};
+use super::index::FileStamp;
+use super::{move_to_quarantine, BrokenDeck, DeckInfo, DeckRepository, DeckVersion};
+use crate::models::{Card, Deck};
+
+const DECK_FILE: &str = "deck.json";
+const CARDS_DIR: &str = "cards";
+
+/// Stores each deck as `<deck_id>/deck.json` plus `<deck_id>/cards/*.json`.
+pub struct DirectoryRepository {
+    dir: PathBuf,
+    files: FileCodec,
+}
+
+impl DirectoryRepository {
+    pub fn new(dir: PathBuf, files: FileCodec) -> Self {
+        Self { dir, files }
+    }
+
+    fn deck_dir(&self, deck_id: &str) -> PathBuf {
+        self.dir.join(deck_id)
+    }
+
+    fn card_path(deck_dir: &Path, card_id: &str) -> PathBuf {
+        deck_dir.join(CARDS_DIR).join(format!("{}.json", card_id))
+    }
+
+    fn write_card(&self, deck_dir: &Path, card: &Card) -> Result<()> {
+        let json = serde_json::to_string_pretty(card)?;
+        self.files.write(&Self::card_path(deck_dir, &card.id), &json)
+    }
+
+    /// Read a deck directory. Cards missing from the stored order (e.g. added
+    /// by hand) are appended in file name order.
+    fn read_deck(&self, deck_dir: &Path) -> Result<Deck> {
+        let meta_path = deck_dir.join(DECK_FILE);
+        let json = self.files.read(&meta_path)?;
+        let mut value: serde_json::Value = serde_json::from_str(&json)?;
+
+        let order: Vec<String> = value
+            .get("card_order")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+
+        let cards_dir = deck_dir.join(CARDS_DIR);
+        let mut unordered: Vec<String> = Vec::new();
+        if cards_dir.is_dir() {
+            for entry in fs::read_dir(&cards_dir)? {
+                let path = entry?.path();
+                if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                    if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+                        if !order.iter().any(|o| o == id) {
+                            unordered.push(id.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        unordered.sort();
+
+        let mut cards = Vec::new();
+        for card_id in order.iter().chain(unordered.iter()) {
+            let path = Self::card_path(deck_dir, card_id);
+            if !path.exists() {
+                continue;
+            }
+            let json = self.files.read(&path)?;
+            let card: serde_json::Value = serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse card {:?}", path))?;
+            cards.push(card);
+        }
+
+        if let Some(obj) = value.as_object_mut() {
+            obj.remove("card_order");
+            obj.insert("cards".to_string(), serde_json::Value::Array(cards));
+        }
+
+        // Migrations see the whole deck, as they would for a JSON file
+        let deck = schema::deck_from_value(value)?;
+        Ok(deck)
+    }
+}
+
+impl DeckRepository for DirectoryRepository {
+    fn load_deck(&self, deck_id: &str) -> Result<Option<Deck>> {
+        let deck_dir = self.deck_dir(deck_id);
+        if !deck_dir.join(DECK_FILE).exists() {
+            return Ok(None);
+        }
+        Ok(Some(self.read_deck(&deck_dir)?))
+    }
+
+    fn save_deck(&self, deck: &Deck) -> Result<()> {
+        let deck_dir = self.deck_dir(&deck.id);
+        let cards_dir = deck_dir.join(CARDS_DIR);
+        fs::create_dir_all(&cards_dir)
+            .with_context(|| format!("Failed to create deck directory: {:?}", cards_dir))?;
+
+        for card in &deck.cards {
+            self.write_card(&deck_dir, card)?;
+        }
+
+        // Remove files of cards that were deleted
+        for entry in fs::read_dir(&cards_dir)? {
+            let path = entry?.path();
+            let keep = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .is_some_and(|id| deck.cards.iter().any(|c| c.id == id));
+            if !keep {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        let mut value = schema::deck_to_value(deck)?;
+        if let Some(obj) = value.as_object_mut() {
+            obj.remove("cards");
+            let order: Vec<&str> = deck.cards.iter().map(|c| c.id.as_str()).collect();
+            obj.insert("card_order".to_string(), serde_json::to_value(order)?);
+        }
+        self.files
+            .write(&deck_dir.join(DECK_FILE), &serde_json::to_string_pretty(&value)?)
+    }
+
+    fn save_card(&self, deck: &Deck, card_idx: usize) -> Result<()> {
+        let deck_dir = self.deck_dir(&deck.id);
+        match deck.cards.get(card_idx) {
+            Some(card) if Self::card_path(&deck_dir, &card.id).exists() => {
+                self.write_card(&deck_dir, card)
+            }
+            _ => self.save_deck(deck),
+        }
+    }
+
+    fn delete_deck(&self, deck_id: &str) -> Result<bool> {
+        let deck_dir = self.deck_dir(deck_id);
+        if deck_dir.join(DECK_FILE).exists() {
+            fs::remove_dir_all(&deck_dir)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Made from the size and modification time of every file in the deck.
+    fn version(&self, deck_id: &str) -> Result<Option<DeckVersion>> {
+        let deck_dir = self.deck_dir(deck_id);
+        let meta_path = deck_dir.join(DECK_FILE);
+        if !meta_path.exists() {
+            return Ok(None);
+        }
+
+        let mut stamps = vec![(DECK_FILE.into(), FileStamp::of(&meta_path))];
+        let cards_dir = deck_dir.join(CARDS_DIR);
+        if cards_dir.is_dir() {
+            for entry in fs::read_dir(&cards_dir)? {
+                let entry = entry?;
+                stamps.push((entry.file_name(), FileStamp::of(&entry.path())));
+            }
+        }
+        stamps.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(Some(DeckVersion::of(stamps)))
+    }
+
+    fn scan_decks(&self) -> Result<(Vec<DeckInfo>, Vec<BrokenDeck>)> {
+        let mut decks = Vec::new();
+        let mut broken = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let deck_dir = entry?.path();
+            let meta_path = deck_dir.join(DECK_FILE);
+            if !meta_path.is_file() {
+                continue;
+            }
+
+            match self.read_deck(&deck_dir) {
+                // Decks are loaded from the directory named after their ID;
+                // another one with the same ID is a stray copy
+                Ok(deck)
+                    if deck_dir.file_name() != Some(deck.id.as_ref())
+                        && self.deck_dir(&deck.id).join(DECK_FILE).exists() =>
+                {
+                    broken.push(BrokenDeck {
+                        path: meta_path,
+                        error: format!("Same deck ID as {}/", deck.id),
+                    })
+                }
+                Ok(deck) => decks.push(DeckInfo::from_deck(&deck)),
+                Err(e) => broken.push(BrokenDeck {
+                    path: meta_path,
+                    error: format!("{:#}", e),
+                }),
+            }
+        }
+
+        decks.sort_by(|a, b| a.name.cmp(&b.name));
+        broken.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok((decks, broken))
+    }
+
+    fn quarantine(&self, broken: &BrokenDeck) -> Result<PathBuf> {
+        // Move the whole deck directory, not just its deck.json
+        let deck_dir = broken
+            .path
+            .parent()
+            .with_context(|| format!("Not a deck directory: {:?}", broken.path))?;
+        move_to_quarantine(&self.dir, deck_dir)
+    }
+
+    fn schema_report(&self) -> Result<Vec<SchemaStatus>> {
+        let mut report = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let meta_path = entry.path().join(DECK_FILE);
+            let value = self
+                .files
+                .read(&meta_path)
+                .ok()
+                .and_then(|json| serde_json::from_str(&json).ok());
+            // Stray copies of another deck are reported as broken decks
+            if let Some(value) = value {
+                let dir_name = entry.file_name().to_string_lossy().into_owned();
+                let status = SchemaStatus::of(dir_name, &value);
+                if status.deck_id.as_ref().is_none_or(|id| status.label == *id) {
+                    report.push(status);
+                }
+            }
+        }
+
+        report.sort_by(|a, b| a.label.cmp(&b.label));
+        Ok(report)
+    }
+}
@@ -0,0 +1,112 @@
+//! Cached summaries of deck files, so listing decks doesn't parse every deck.
+//!
+//! Entries are keyed by file name and trusted only while the file's size and
+//! modification time match. Due counts also depend on the clock, so each entry
+//! records when its earliest scheduled card falls due and is rebuilt after that.
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use super::crypto::FileCodec;
+use super::DeckInfo;
+use crate::models::Deck;
+
+/// Index file name inside the decks directory.
+pub const INDEX_FILE: &str = ".deck_index";
+
+/// Size and modification time of a deck file when it was indexed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileStamp {
+    len: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            len: meta.len(),
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    stamp: FileStamp,
+    info: DeckInfo,
+    /// When the next scheduled card becomes due or buried card comes back,
+    /// changing the counts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recheck_at: Option<DateTime<Local>>,
+}
+
+/// Summaries of the deck files in a directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeckIndex {
+    entries: HashMap<String, IndexEntry>,
+}
+
+impl DeckIndex {
+    /// Load the index for a directory. A missing or unreadable index is empty.
+    pub fn load(dir: &Path, files: &FileCodec) -> Self {
+        files
+            .read(&dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the index, encrypted along with the decks it summarizes.
+    pub fn save(&self, dir: &Path, files: &FileCodec) -> Result<()> {
+        files.write(&dir.join(INDEX_FILE), &serde_json::to_string(self)?)
+    }
+
+    /// Cached summary for a file, if it is still current.
+    pub fn lookup(&self, file_name: &str, stamp: FileStamp) -> Option<&DeckInfo> {
+        let entry = self.entries.get(file_name)?;
+        if entry.stamp != stamp {
+            return None;
+        }
+        if entry.recheck_at.is_some_and(|at| Local::now() >= at) {
+            return None;
+        }
+        Some(&entry.info)
+    }
+
+    pub fn insert(&mut self, file_name: String, stamp: FileStamp, deck: &Deck) {
+        let now = Local::now();
+        let recheck_at = deck
+            .cards
+            .iter()
+            .filter(|c| !c.is_new())
+            .filter_map(|c| c.due_date)
+            .chain(deck.cards.iter().filter_map(|c| c.buried_until))
+            .filter(|at| *at > now)
+            .min();
+
+        self.entries.insert(
+            file_name,
+            IndexEntry {
+                stamp,
+                info: DeckInfo::from_deck(deck),
+                recheck_at,
+            },
+        );
+    }
+
+    /// Drop entries for files that no longer exist. Returns whether any were removed.
+    pub fn retain_files(&mut self, present: &HashSet<String>) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|name, _| present.contains(name));
+        self.entries.len() != before
+    }
+}
@@ -0,0 +1,161 @@
+//! JSON backend: one pretty-printed file per deck in the decks directory.
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use super::crypto::FileCodec;
+use super::index::{DeckIndex, FileStamp};
+use super::schema::{self, SchemaStatus};
+use super::{move_to_quarantine, BrokenDeck, DeckInfo, DeckRepository, DeckVersion};
+use crate::models::Deck;
+
+/// Stores each deck as `<deck_id>.json`.
+pub struct JsonRepository {
+    dir: PathBuf,
+    files: FileCodec,
+}
+
+impl JsonRepository {
+    pub fn new(dir: PathBuf, files: FileCodec) -> Self {
+        Self { dir, files }
+    }
+
+    fn deck_path(&self, deck_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", deck_id))
+    }
+}
+
+impl DeckRepository for JsonRepository {
+    fn load_deck(&self, deck_id: &str) -> Result<Option<Deck>> {
+        let path = self.deck_path(deck_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = self.files.read(&path)?;
+        let deck = schema::parse_deck(&json)?;
+        Ok(Some(deck))
+    }
+
+    fn save_deck(&self, deck: &Deck) -> Result<()> {
+        let path = self.deck_path(&deck.id);
+        let json = serde_json::to_string_pretty(&schema::deck_to_value(deck)?)?;
+        self.files.write(&path, &json)
+    }
+
+    fn delete_deck(&self, deck_id: &str) -> Result<bool> {
+        let path = self.deck_path(deck_id);
+        if path.exists() {
+            fs::remove_file(&path)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn version(&self, deck_id: &str) -> Result<Option<DeckVersion>> {
+        let path = self.deck_path(deck_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let stamp = FileStamp::of(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        Ok(Some(DeckVersion::of(stamp)))
+    }
+
+    fn scan_decks(&self) -> Result<(Vec<DeckInfo>, Vec<BrokenDeck>)> {
+        let mut decks = Vec::new();
+        let mut broken = Vec::new();
+
+        // Only files changed since the last scan are parsed
+        let mut index = DeckIndex::load(&self.dir, &self.files);
+        let mut index_changed = false;
+        let mut seen = HashSet::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let stamp = FileStamp::of(&path);
+            seen.insert(file_name.clone());
+
+            let info = match stamp.and_then(|s| index.lookup(&file_name, s)) {
+                Some(info) => Ok(info.clone()),
+                None => self
+                    .files
+                    .read(&path)
+                    .and_then(|json| schema::parse_deck(&json))
+                    .map(|deck| {
+                        if let Some(stamp) = stamp {
+                            index.insert(file_name.clone(), stamp, &deck);
+                            index_changed = true;
+                        }
+                        DeckInfo::from_deck(&deck)
+                    }),
+            };
+
+            match info {
+                // Decks are loaded from `<id>.json`; another file with the same ID is a stray copy
+                Ok(info) if file_name != format!("{}.json", info.id) && self.deck_path(&info.id).exists() => {
+                    broken.push(BrokenDeck {
+                        path,
+                        error: format!("Same deck ID as {}.json", info.id),
+                    })
+                }
+                Ok(info) => decks.push(info),
+                Err(e) => broken.push(BrokenDeck {
+                    path,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        if index.retain_files(&seen) || index_changed {
+            // The index is only a cache; failing to write it just means reparsing next time
+            let _ = index.save(&self.dir, &self.files);
+        }
+
+        decks.sort_by(|a, b| a.name.cmp(&b.name));
+        broken.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok((decks, broken))
+    }
+
+    fn quarantine(&self, broken: &BrokenDeck) -> Result<PathBuf> {
+        move_to_quarantine(&self.dir, &broken.path)
+    }
+
+    fn schema_report(&self) -> Result<Vec<SchemaStatus>> {
+        let mut report = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            // Unparseable files and stray copies are already reported as broken decks
+            let value = self
+                .files
+                .read(&path)
+                .ok()
+                .and_then(|json| serde_json::from_str(&json).ok());
+            if let Some(value) = value {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                let status = SchemaStatus::of(file_name, &value);
+                if status.deck_id.as_ref().is_none_or(|id| status.label == format!("{}.json", id)) {
+                    report.push(status);
+                }
+            }
+        }
+
+        report.sort_by(|a, b| a.label.cmp(&b.label));
+        Ok(report)
+    }
+}
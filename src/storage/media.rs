@@ -0,0 +1,163 @@
+//! Media files (images and sounds) referenced from cards.
+//!
+//! Each deck's media lives in `media/<deck id>/` inside the decks directory,
+//! one file per name referenced from its cards (see `models::media_refs`).
+//! Encrypted decks have their media encrypted too.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::crypto::FileCodec;
+use super::DeckStorage;
+use crate::sync::protocol::valid_entity_id;
+
+/// Directory inside the decks directory holding the decks' media folders.
+pub const MEDIA_DIR: &str = "media";
+
+/// Whether a media name is a plain file name, so it can't point outside the
+/// deck's media folder. Names come from imported files, so this matters.
+pub fn is_valid_media_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\', '\0'])
+}
+
+/// Whether a deck or card ID is safe to name files after, as the storage
+/// backends do. IDs in imported and synced decks come from other people's
+/// files, so check them the same way. The sync server checks pushes with the
+/// same rule, so it lives in the protocol module the server compiles.
+pub fn is_valid_id(id: &str) -> bool {
+    valid_entity_id(id)
+}
+
+impl DeckStorage {
+    /// Folder holding a deck's media, for storage backed by a directory.
+    pub fn media_dir(&self, deck_id: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(MEDIA_DIR).join(deck_id))
+    }
+
+    fn media_path(&self, deck_id: &str, name: &str) -> Result<PathBuf> {
+        if !is_valid_media_name(name) {
+            anyhow::bail!("Invalid media file name {:?}", name);
+        }
+        let dir = self
+            .media_dir(deck_id)
+            .context("Media needs decks stored in a directory")?;
+        Ok(dir.join(name))
+    }
+
+    /// Store a media file for a deck, replacing any file of the same name.
+    pub fn save_media(&self, deck_id: &str, name: &str, data: &[u8]) -> Result<()> {
+        let path = self.media_path(deck_id, name)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        }
+        self.files
+            .write_bytes(&path, data)
+            .with_context(|| format!("Failed to write media file {:?}", path))
+    }
+
+    /// Read a deck's media file, or `None` if it doesn't have one by that name.
+    pub fn load_media(&self, deck_id: &str, name: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.media_path(deck_id, name)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let data = self
+            .files
+            .read_bytes(&path)
+            .with_context(|| format!("Failed to read media file {:?}", path))?;
+        Ok(Some(data))
+    }
+
+    /// A path other programs can open a media file from: the file itself, or
+    /// a decrypted copy if the decks are encrypted. Copies go in a folder only
+    /// the user can read, removed when the storage is dropped.
+    pub fn media_for_viewing(&self, deck_id: &str, name: &str) -> Result<PathBuf> {
+        let path = self.media_path(deck_id, name)?;
+        if !path.is_file() {
+            anyhow::bail!("Media file {} is missing", name);
+        }
+        if !self.files.is_encrypted() {
+            return Ok(path);
+        }
+
+        let data = self
+            .files
+            .read_bytes(&path)
+            .with_context(|| format!("Failed to read media file {:?}", path))?;
+        let copy_dir = self.viewing_dir()?.join(deck_id);
+        fs::create_dir_all(&copy_dir).with_context(|| format!("Failed to create {:?}", copy_dir))?;
+        let copy = copy_dir.join(name);
+        fs::write(&copy, data).with_context(|| format!("Failed to write {:?}", copy))?;
+        Ok(copy)
+    }
+
+    /// This process's folder for decrypted media, created on first use with
+    /// access for the user only.
+    fn viewing_dir(&self) -> Result<PathBuf> {
+        let mut viewing_dir = self.viewing_dir.borrow_mut();
+        if let Some(ref dir) = *viewing_dir {
+            return Ok(dir.path().to_path_buf());
+        }
+        let mut builder = tempfile::Builder::new();
+        builder.prefix("srl-media-");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            builder.permissions(fs::Permissions::from_mode(0o700));
+        }
+        let dir = builder
+            .tempdir()
+            .context("Failed to create a folder for decrypted media")?;
+        let path = dir.path().to_path_buf();
+        *viewing_dir = Some(dir);
+        Ok(path)
+    }
+
+    /// Remove a deck's media folder along with the deck.
+    pub(super) fn delete_media(&self, deck_id: &str) -> Result<()> {
+        match self.media_dir(deck_id) {
+            Some(dir) if dir.is_dir() => {
+                fs::remove_dir_all(&dir).with_context(|| format!("Failed to remove {:?}", dir))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Write every media file in `dir`, written with `old`, to the same place
+    /// under `to` with `new`.
+    pub(super) fn reencode_media(dir: &Path, to: &Path, old: &FileCodec, new: &FileCodec) -> Result<()> {
+        let root = dir.join(MEDIA_DIR);
+        if !root.is_dir() {
+            return Ok(());
+        }
+
+        for deck_dir in fs::read_dir(&root)? {
+            let deck_dir = deck_dir?.path();
+            if !deck_dir.is_dir() {
+                continue;
+            }
+            let Some(deck_name) = deck_dir.file_name() else {
+                continue;
+            };
+            let target_dir = to.join(MEDIA_DIR).join(deck_name);
+            fs::create_dir_all(&target_dir)
+                .with_context(|| format!("Failed to create media directory {:?}", target_dir))?;
+            for file in fs::read_dir(&deck_dir)? {
+                let path = file?.path();
+                let (true, Some(name)) = (path.is_file(), path.file_name()) else {
+                    continue;
+                };
+                let data = old
+                    .read_bytes(&path)
+                    .with_context(|| format!("Failed to read media file {:?}", path))?;
+                let target = target_dir.join(name);
+                new.write_bytes(&target, &data)
+                    .with_context(|| format!("Failed to write media file {:?}", target))?;
+            }
+        }
+        Ok(())
+    }
+}
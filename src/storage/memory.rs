@@ -0,0 +1,54 @@
+//! In-memory backend: nothing touches the disk.
+
+use anyhow::Result;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use super::{BrokenDeck, DeckInfo, DeckRepository};
+use crate::models::Deck;
+
+/// Keeps decks in memory for the lifetime of the process.
+#[derive(Default)]
+pub struct MemoryRepository {
+    decks: RefCell<BTreeMap<String, Deck>>,
+}
+
+impl MemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DeckRepository for MemoryRepository {
+    fn load_deck(&self, deck_id: &str) -> Result<Option<Deck>> {
+        Ok(self.decks.borrow().get(deck_id).cloned())
+    }
+
+    fn save_deck(&self, deck: &Deck) -> Result<()> {
+        self.decks.borrow_mut().insert(deck.id.clone(), deck.clone());
+        Ok(())
+    }
+
+    fn save_card(&self, deck: &Deck, card_idx: usize) -> Result<()> {
+        let mut decks = self.decks.borrow_mut();
+        match (decks.get_mut(&deck.id), deck.cards.get(card_idx)) {
+            (Some(stored), Some(card)) if stored.cards.len() == deck.cards.len() => {
+                stored.cards[card_idx] = card.clone();
+            }
+            _ => {
+                decks.insert(deck.id.clone(), deck.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_deck(&self, deck_id: &str) -> Result<bool> {
+        Ok(self.decks.borrow_mut().remove(deck_id).is_some())
+    }
+
+    fn scan_decks(&self) -> Result<(Vec<DeckInfo>, Vec<BrokenDeck>)> {
+        let mut decks: Vec<DeckInfo> = self.decks.borrow().values().map(DeckInfo::from_deck).collect();
+        decks.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok((decks, Vec::new()))
+    }
+}
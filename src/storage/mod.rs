@@ -0,0 +1,851 @@
+//! Storage module for saving and loading flashcard decks.
+
+use anyhow::{Context, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use crate::models::Deck;
+use crate::sync::{merge_decks, ContentConflict};
+use crypto::{dir_is_encrypted, FileCodec};
+
+mod backups;
+mod conflict;
+mod crypto;
+mod directory;
+mod index;
+mod json;
+mod media;
+mod memory;
+mod remote;
+pub mod schema;
+pub mod sqlite;
+mod watch;
+
+pub use crate::config::StorageBackend;
+pub use backups::{BackupEntry, BackupRotation};
+pub use conflict::{DeckConflict, DirLock};
+pub use crypto::{Cipher, Locked};
+pub use directory::DirectoryRepository;
+pub use json::JsonRepository;
+pub use media::{is_valid_id, is_valid_media_name};
+pub use memory::MemoryRepository;
+pub use schema::SchemaStatus;
+pub use sqlite::SqliteRepository;
+pub use watch::DeckWatcher;
+
+/// Folder in the decks directory that `set_passphrase` stages files in.
+const REKEY_DIR: &str = ".srl-rekey";
+/// Staging subfolders for the decks and for everything else.
+const STAGED_DECKS: &str = "decks";
+const STAGED_OTHER: &str = "other";
+
+/// Bundled deck: Development Workflow
+const BUNDLED_DEV_WORKFLOW: &str = include_str!("../../bundled_decks/development-workflow.json");
+
+/// Where decks are persisted.
+///
+/// Implemented once per storage backend; `DeckStorage` builds backups and
+/// bulk operations on top of these primitives.
+pub trait DeckRepository {
+    /// Load a deck, returning `None` if it doesn't exist.
+    fn load_deck(&self, deck_id: &str) -> Result<Option<Deck>>;
+
+    /// Save a deck, replacing any existing copy.
+    fn save_deck(&self, deck: &Deck) -> Result<()>;
+
+    /// Persist a change to a single card of a deck.
+    /// Backends without per-card storage rewrite the whole deck.
+    fn save_card(&self, deck: &Deck, _card_idx: usize) -> Result<()> {
+        self.save_deck(deck)
+    }
+
+    /// Delete a deck, returning whether it existed.
+    fn delete_deck(&self, deck_id: &str) -> Result<bool>;
+
+    /// A stamp that changes whenever the stored deck does, or `None` if the
+    /// deck doesn't exist. Checked before every save, so backends should
+    /// answer without reading the whole deck; the default hashes it.
+    fn version(&self, deck_id: &str) -> Result<Option<DeckVersion>> {
+        let Some(deck) = self.load_deck(deck_id)? else {
+            return Ok(None);
+        };
+        let json = serde_json::to_string(&schema::deck_to_value(&deck)?)?;
+        Ok(Some(DeckVersion::of(&json)))
+    }
+
+    /// List all decks sorted by name, along with any that could not be read.
+    fn scan_decks(&self) -> Result<(Vec<DeckInfo>, Vec<BrokenDeck>)>;
+
+    /// Move an unreadable deck out of the way so it no longer shows up.
+    /// Returns its new location.
+    fn quarantine(&self, broken: &BrokenDeck) -> Result<PathBuf> {
+        anyhow::bail!("This storage backend cannot quarantine {:?}", broken.path)
+    }
+
+    /// Format version of each stored deck, for reporting pending migrations.
+    /// Backends that don't persist anything have nothing to report.
+    fn schema_report(&self) -> Result<Vec<SchemaStatus>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Identifies one state of a stored deck; see `DeckRepository::version`.
+/// Only meaningful within one process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeckVersion(u64);
+
+impl DeckVersion {
+    /// A version made by hashing `value`.
+    pub fn of(value: impl Hash) -> Self {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+}
+
+/// A deck as this process last loaded or saved it.
+struct LoadedDeck {
+    deck: Deck,
+    /// Stored version at the time, or `None` if it couldn't be read.
+    version: Option<DeckVersion>,
+}
+
+/// Handles deck persistence.
+pub struct DeckStorage {
+    repo: Box<dyn DeckRepository>,
+    /// Directory the decks live in, locked while saving. `None` for storage
+    /// that isn't backed by a directory.
+    dir: Option<PathBuf>,
+    backend: Option<StorageBackend>,
+    /// Reads and writes the decks directory's files, encrypting them if it is
+    /// encrypted.
+    files: FileCodec,
+    /// Each deck as last loaded or saved, to detect changes made elsewhere.
+    loaded: RefCell<HashMap<String, LoadedDeck>>,
+    /// Private folder for decrypted copies of media opened in other
+    /// programs, created when first needed and removed on drop.
+    viewing_dir: RefCell<Option<tempfile::TempDir>>,
+}
+
+impl DeckStorage {
+    /// Open storage in `decks_dir` using the given backend.
+    /// Fails if the decks are encrypted; open those with `open_encrypted`.
+    pub fn open(decks_dir: PathBuf, backend: StorageBackend) -> Result<Self> {
+        if Self::needs_passphrase(&decks_dir, backend) {
+            anyhow::bail!("The decks in {:?} are encrypted; a passphrase is needed to open them", decks_dir);
+        }
+        Self::open_with(decks_dir, backend, FileCodec::plain())
+    }
+
+    /// Whether the decks in `decks_dir` are encrypted, so opening them needs
+    /// a passphrase.
+    pub fn needs_passphrase(decks_dir: &Path, backend: StorageBackend) -> bool {
+        supports_encryption(backend) && dir_is_encrypted(decks_dir)
+    }
+
+    /// Open encrypted decks, unlocked with a cipher from `Cipher::unlock`.
+    pub fn open_encrypted(decks_dir: PathBuf, backend: StorageBackend, cipher: Cipher) -> Result<Self> {
+        if !supports_encryption(backend) {
+            anyhow::bail!("Encrypted decks need the json or directory storage backend");
+        }
+        Self::open_with(decks_dir, backend, FileCodec::encrypted(cipher))
+    }
+
+    fn open_with(decks_dir: PathBuf, backend: StorageBackend, files: FileCodec) -> Result<Self> {
+        if backend != StorageBackend::Memory {
+            fs::create_dir_all(&decks_dir)
+                .with_context(|| format!("Failed to create decks directory: {:?}", decks_dir))?;
+        }
+
+        let dir = (backend != StorageBackend::Memory).then(|| decks_dir.clone());
+        let repo = open_repository(decks_dir, backend, &files)?;
+
+        let mut storage = Self::with_repository(repo);
+        storage.dir = dir;
+        storage.backend = Some(backend);
+        storage.files = files;
+        storage.install_bundled_decks();
+        Ok(storage)
+    }
+
+    /// Wrap an existing repository, e.g. a third-party backend.
+    pub fn with_repository(repo: Box<dyn DeckRepository>) -> Self {
+        Self {
+            repo,
+            dir: None,
+            backend: None,
+            files: FileCodec::plain(),
+            loaded: RefCell::new(HashMap::new()),
+            viewing_dir: RefCell::new(None),
+        }
+    }
+
+    /// Install bundled decks if they don't already exist.
+    fn install_bundled_decks(&self) {
+        // Check if any decks exist - if so, user has already used the app
+        if let Ok((decks, broken)) = self.repo.scan_decks() {
+            if !decks.is_empty() || !broken.is_empty() {
+                return; // User already has decks, don't overwrite
+            }
+        }
+
+        // Install bundled decks for first-time users
+        if let Ok(mut deck) = schema::parse_deck(BUNDLED_DEV_WORKFLOW) {
+            // Reset all cards to fresh state
+            for card in &mut deck.cards {
+                card.reset_progress();
+            }
+            let _ = self.save_deck(&deck);
+        }
+    }
+
+    /// Get default storage location.
+    pub fn default_path() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("flashcards")
+            .join("decks")
+    }
+
+    /// Save a deck.
+    /// Fails with `DeckConflict` if the stored copy changed since it was loaded.
+    pub fn save_deck(&self, deck: &Deck) -> Result<()> {
+        let _lock = self.lock()?;
+        self.check_unchanged(deck)?;
+        self.repo.save_deck(deck)?;
+        self.remember(deck, self.repo.version(&deck.id)?);
+        Ok(())
+    }
+
+    /// Save a single reviewed or edited card of a deck.
+    /// Fails with `DeckConflict` if the stored copy changed since it was loaded.
+    pub fn save_card(&self, deck: &Deck, card_idx: usize) -> Result<()> {
+        let _lock = self.lock()?;
+        self.check_unchanged(deck)?;
+        self.repo.save_card(deck, card_idx)?;
+        self.remember(deck, self.repo.version(&deck.id)?);
+        Ok(())
+    }
+
+    /// Save a deck even if it changed elsewhere, discarding those changes.
+    pub fn overwrite_deck(&self, deck: &Deck) -> Result<()> {
+        let _lock = self.lock()?;
+        self.repo.save_deck(deck)?;
+        self.remember(deck, self.repo.version(&deck.id)?);
+        Ok(())
+    }
+
+    /// Merge a deck with the changes made to it elsewhere since it was
+    /// loaded, the same way syncs merge, then save and return the result.
+    /// Cards whose text was changed differently in both places keep this
+    /// copy's text and are returned as conflicts.
+    pub fn merge_deck(&self, deck: &Deck) -> Result<(Deck, Vec<ContentConflict>)> {
+        let _lock = self.lock()?;
+        let base = self.loaded.borrow().get(&deck.id).map(|l| l.deck.clone());
+        let mut conflicts = Vec::new();
+        let merged = match self.repo.load_deck(&deck.id)? {
+            Some(theirs) => merge_decks(base.as_ref(), deck, &theirs, &mut conflicts),
+            // Deleted elsewhere: keeping ours re-creates it
+            None => deck.clone(),
+        };
+        self.repo.save_deck(&merged)?;
+        self.remember(&merged, self.repo.version(&deck.id)?);
+        Ok((merged, conflicts))
+    }
+
+    /// Load a deck.
+    pub fn load_deck(&self, deck_id: &str) -> Result<Option<Deck>> {
+        // Taken first, so a change made while loading shows up as a conflict
+        let version = self.repo.version(deck_id).ok().flatten();
+        let deck = self.repo.load_deck(deck_id)?;
+        if let Some(ref deck) = deck {
+            self.remember(deck, version);
+        }
+        Ok(deck)
+    }
+
+    /// Delete a deck.
+    pub fn delete_deck(&self, deck_id: &str) -> Result<bool> {
+        let _lock = self.lock()?;
+        self.loaded.borrow_mut().remove(deck_id);
+        let existed = self.repo.delete_deck(deck_id)?;
+        self.delete_media(deck_id)?;
+        Ok(existed)
+    }
+
+    /// Watch the decks directory for changes made by other programs.
+    /// Returns `None` for storage that isn't backed by a directory.
+    pub fn watch(&self) -> Result<Option<DeckWatcher>> {
+        self.dir.as_deref().map(DeckWatcher::new).transpose()
+    }
+
+    /// Whether a deck differs from the copy last loaded or saved.
+    pub fn has_unsaved_changes(&self, deck: &Deck) -> bool {
+        self.loaded.borrow().get(&deck.id).is_some_and(|loaded| loaded.deck != *deck)
+    }
+
+    /// Directory the decks live in, if they live in one.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Backend the storage was opened with, if opened with `open`.
+    pub fn backend(&self) -> Option<StorageBackend> {
+        self.backend
+    }
+
+    /// Whether the decks are encrypted at rest.
+    pub fn is_encrypted(&self) -> bool {
+        self.files.is_encrypted()
+    }
+
+    /// Encrypt the decks with a new passphrase, or decrypt them with `None`.
+    /// Backups made so far are left as they were. Files are re-encoded into a
+    /// staging folder and then moved into place, the key file last; an error
+    /// while moving names the decks already rewritten.
+    /// Returns the number of decks rewritten.
+    pub fn set_passphrase(&mut self, passphrase: Option<&str>) -> Result<usize> {
+        let (Some(dir), Some(backend)) = (self.dir.clone(), self.backend) else {
+            anyhow::bail!("Only decks stored in a directory can be encrypted");
+        };
+        if !supports_encryption(backend) {
+            anyhow::bail!("Encryption needs the json or directory storage backend");
+        }
+
+        let _lock = self.lock()?;
+        let (infos, broken) = self.repo.scan_decks()?;
+        if !broken.is_empty() {
+            anyhow::bail!(
+                "{} deck files can't be read; repair or quarantine them first",
+                broken.len()
+            );
+        }
+        let mut decks = Vec::new();
+        for info in infos {
+            decks.extend(self.repo.load_deck(&info.id)?);
+        }
+
+        let files = match passphrase {
+            Some(passphrase) => FileCodec::encrypted(Cipher::new(passphrase)?),
+            None => FileCodec::plain(),
+        };
+
+        // Everything is re-encoded into a staging folder first, so a failure
+        // there leaves the directory as it was
+        let staging = dir.join(REKEY_DIR);
+        if staging.exists() {
+            fs::remove_dir_all(&staging)
+                .with_context(|| format!("Failed to clear {:?}", staging))?;
+        }
+        let staged = Self::stage_reencoded(&dir, &staging, backend, &self.files, &files, &decks);
+        if let Err(e) = staged {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+
+        // Then moved into place one deck at a time, with the key file last
+        let mut moved: Vec<&str> = Vec::new();
+        for (i, deck) in decks.iter().enumerate() {
+            if let Err(e) = move_tree(&staging.join(STAGED_DECKS).join(i.to_string()), &dir) {
+                return Err(rekey_failed(e, &moved, &staging));
+            }
+            moved.push(&deck.name);
+        }
+        let key_file = dir.join(crypto::KEY_FILE);
+        let finish = move_tree(&staging.join(STAGED_OTHER), &dir).and_then(|()| {
+            let staged_key = staging.join(crypto::KEY_FILE);
+            if staged_key.exists() {
+                fs::rename(&staged_key, &key_file)
+                    .with_context(|| format!("Failed to move {:?} to {:?}", staged_key, key_file))?;
+            } else if key_file.exists() {
+                fs::remove_file(&key_file).with_context(|| format!("Failed to remove {:?}", key_file))?;
+            }
+            Ok(())
+        });
+        if let Err(e) = finish {
+            return Err(rekey_failed(e, &moved, &staging));
+        }
+        let _ = fs::remove_dir_all(&staging);
+        // Rebuilt on the next scan
+        let _ = fs::remove_file(dir.join(index::INDEX_FILE));
+
+        self.repo = open_repository(dir, backend, &files)?;
+        self.files = files;
+        Ok(decks.len())
+    }
+
+    /// Write `decks`, the sync state and the media of `dir` under `staging`
+    /// with `new`: each deck in its own `STAGED_DECKS/<n>` folder, laid out as
+    /// in `dir`, the rest in `STAGED_OTHER`, and any key file in `staging`.
+    fn stage_reencoded(
+        dir: &Path,
+        staging: &Path,
+        backend: StorageBackend,
+        old: &FileCodec,
+        new: &FileCodec,
+        decks: &[Deck],
+    ) -> Result<()> {
+        for (i, deck) in decks.iter().enumerate() {
+            let deck_dir = staging.join(STAGED_DECKS).join(i.to_string());
+            fs::create_dir_all(&deck_dir)
+                .with_context(|| format!("Failed to create {:?}", deck_dir))?;
+            open_repository(deck_dir, backend, new)?
+                .save_deck(deck)
+                .with_context(|| format!("Failed to rewrite deck '{}'", deck.name))?;
+        }
+
+        let other = staging.join(STAGED_OTHER);
+        fs::create_dir_all(&other).with_context(|| format!("Failed to create {:?}", other))?;
+        Self::reencode_sync_state(dir, &other, old, new)?;
+        Self::reencode_media(dir, &other, old, new)?;
+        if let Some(cipher) = new.cipher() {
+            cipher.write_key_file(staging)?;
+        }
+        Ok(())
+    }
+
+    /// Hold off saves from this and other srl processes until dropped.
+    pub fn lock(&self) -> Result<Option<DirLock>> {
+        self.dir.as_deref().map(DirLock::acquire).transpose()
+    }
+
+    fn remember(&self, deck: &Deck, version: Option<DeckVersion>) {
+        let loaded = LoadedDeck {
+            deck: deck.clone(),
+            version,
+        };
+        self.loaded.borrow_mut().insert(deck.id.clone(), loaded);
+    }
+
+    /// Fail if the stored deck changed since we loaded or saved it.
+    /// Decks this process never loaded (e.g. fresh imports) aren't checked.
+    fn check_unchanged(&self, deck: &Deck) -> Result<()> {
+        let Some(version) = self.loaded.borrow().get(&deck.id).map(|l| l.version) else {
+            return Ok(());
+        };
+
+        let stored = self.repo.version(&deck.id)?;
+        if stored.is_none() || stored != version {
+            return Err(DeckConflict {
+                deck_id: deck.id.clone(),
+                deck_name: deck.name.clone(),
+                deleted: stored.is_none(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// List all available decks.
+    /// Decks that fail to read or parse are left out; use `scan_decks` to see them.
+    pub fn list_decks(&self) -> Result<Vec<DeckInfo>> {
+        let (decks, _) = self.scan_decks()?;
+        Ok(decks)
+    }
+
+    /// List all decks, also collecting deck files that could not be read or parsed.
+    /// Returns (decks, broken) tuple.
+    pub fn scan_decks(&self) -> Result<(Vec<DeckInfo>, Vec<BrokenDeck>)> {
+        self.repo.scan_decks()
+    }
+
+    /// Copy every deck into another repository, skipping IDs it already has.
+    /// Used for one-shot migration between backends.
+    /// Returns (copied, skipped) tuple.
+    pub fn copy_into(&self, target: &dyn DeckRepository) -> Result<(usize, usize)> {
+        let existing: std::collections::HashSet<String> = target
+            .scan_decks()?
+            .0
+            .into_iter()
+            .map(|d| d.id)
+            .collect();
+
+        let mut copied = 0;
+        let mut skipped = 0;
+        for info in self.list_decks()? {
+            if existing.contains(&info.id) {
+                skipped += 1;
+                continue;
+            }
+            if let Some(deck) = self.load_deck(&info.id)? {
+                target.save_deck(&deck)?;
+                copied += 1;
+            }
+        }
+
+        Ok((copied, skipped))
+    }
+
+    /// Format version of each stored deck and the migrations it needs.
+    pub fn schema_report(&self) -> Result<Vec<SchemaStatus>> {
+        self.repo.schema_report()
+    }
+
+    /// Rewrite every deck stored in an older format in the current one.
+    /// Decks are migrated on load anyway; this just makes it permanent.
+    /// Returns the number of decks rewritten.
+    pub fn apply_migrations(&self) -> Result<usize> {
+        let mut migrated = 0;
+        for status in self.schema_report()? {
+            let Some(deck_id) = status.deck_id.as_deref().filter(|_| status.needs_migration()) else {
+                continue;
+            };
+            let deck = self
+                .load_deck(deck_id)?
+                .with_context(|| format!("Deck {} disappeared during migration", deck_id))?;
+            self.save_deck(&deck)?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+
+    /// Move an unreadable deck out of the way so it no longer shows up.
+    /// Returns its new location.
+    pub fn quarantine_deck(&self, broken: &BrokenDeck) -> Result<PathBuf> {
+        self.repo.quarantine(broken)
+    }
+
+    /// Load the given decks, or every deck when `deck_ids` is `None`.
+    /// Decks that fail to load are skipped.
+    pub fn load_decks(&self, deck_ids: Option<&[String]>) -> Result<Vec<Deck>> {
+        let ids: Vec<String> = match deck_ids {
+            Some(ids) => ids.to_vec(),
+            None => self.list_decks()?.into_iter().map(|d| d.id).collect(),
+        };
+
+        Ok(ids
+            .iter()
+            .filter_map(|id| self.load_deck(id).ok().flatten())
+            .collect())
+    }
+
+    /// Check if a deck with the given name already exists.
+    pub fn deck_name_exists(&self, name: &str) -> bool {
+        self.list_decks()
+            .map(|decks| decks.iter().any(|d| d.name.to_lowercase() == name.to_lowercase()))
+            .unwrap_or(false)
+    }
+}
+
+/// Summary info for a deck.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DeckInfo {
+    pub id: String,
+    pub name: String,
+    pub card_count: usize,
+    pub new_count: usize,
+    pub due_count: usize,
+    pub description: String,
+}
+
+impl DeckInfo {
+    pub fn from_deck(deck: &Deck) -> Self {
+        let stats = deck.get_stats();
+        Self {
+            id: deck.id.clone(),
+            name: deck.name.clone(),
+            card_count: stats.total_cards,
+            new_count: stats.new_cards,
+            due_count: stats.due_cards,
+            description: deck.description.clone(),
+        }
+    }
+}
+
+/// Whether `backend` can store decks encrypted.
+fn supports_encryption(backend: StorageBackend) -> bool {
+    matches!(backend, StorageBackend::Json | StorageBackend::Directory)
+}
+
+fn open_repository(
+    decks_dir: PathBuf,
+    backend: StorageBackend,
+    files: &FileCodec,
+) -> Result<Box<dyn DeckRepository>> {
+    Ok(match backend {
+        StorageBackend::Json => Box::new(JsonRepository::new(decks_dir, files.clone())),
+        StorageBackend::Directory => Box::new(DirectoryRepository::new(decks_dir, files.clone())),
+        StorageBackend::Sqlite => Box::new(SqliteRepository::open(&decks_dir.join(sqlite::DATABASE_FILE))?),
+        StorageBackend::Memory => Box::new(MemoryRepository::new()),
+    })
+}
+
+/// Move every file under `from` to the same place under `to`, replacing
+/// what is there.
+fn move_tree(from: &Path, to: &Path) -> Result<()> {
+    for entry in fs::read_dir(from).with_context(|| format!("Failed to read {:?}", from))? {
+        let path = entry?.path();
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        let target = to.join(name);
+        if path.is_dir() {
+            fs::create_dir_all(&target)
+                .with_context(|| format!("Failed to create {:?}", target))?;
+            move_tree(&path, &target)?;
+        } else {
+            fs::rename(&path, &target)
+                .with_context(|| format!("Failed to move {:?} to {:?}", path, target))?;
+        }
+    }
+    Ok(())
+}
+
+/// The error for a `set_passphrase` that failed while moving files into
+/// place, naming the decks already rewritten.
+fn rekey_failed(error: anyhow::Error, moved: &[&str], staging: &Path) -> anyhow::Error {
+    let moved = if moved.is_empty() {
+        "none".to_string()
+    } else {
+        moved.join(", ")
+    };
+    error.context(format!(
+        "Decks rewritten before the failure: {}. The other re-encoded files are in {:?}",
+        moved, staging
+    ))
+}
+
+/// Move a file or directory into `root/quarantine/`, keeping its name.
+/// Returns the new location.
+fn move_to_quarantine(root: &Path, path: &Path) -> Result<PathBuf> {
+    let dir = root.join("quarantine");
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create quarantine directory: {:?}", dir))?;
+
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Not a deck file: {:?}", path))?;
+    let mut target = dir.join(file_name);
+    if target.exists() {
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        target = dir.join(format!("{}.{}", file_name.to_string_lossy(), timestamp));
+    }
+
+    fs::rename(path, &target)
+        .with_context(|| format!("Failed to move {:?} to {:?}", path, target))?;
+    Ok(target)
+}
+
+/// A deck file that could not be read or parsed.
+#[derive(Debug, Clone)]
+pub struct BrokenDeck {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// What to do with backed-up decks whose ID already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum RestoreMode {
+    /// Leave the existing deck alone
+    #[default]
+    Skip,
+    /// Replace the existing deck with the backed-up copy
+    Overwrite,
+    /// Merge card by card, keeping whichever copy was reviewed more recently
+    Merge,
+    /// Import the backed-up copy as a new, renamed deck
+    Rename,
+}
+
+/// Outcome of restoring a backup.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RestoreReport {
+    /// Decks that didn't exist yet.
+    pub imported: usize,
+    pub skipped: usize,
+    pub replaced: usize,
+    pub merged: usize,
+    pub renamed: usize,
+    /// Nothing was saved.
+    pub dry_run: bool,
+}
+
+impl std::fmt::Display for RestoreReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verb = if self.dry_run { "Would import" } else { "Imported" };
+        write!(f, "{} {} decks", verb, self.imported)?;
+        let others = [
+            (self.replaced, "replaced"),
+            (self.merged, "merged"),
+            (self.renamed, "restored as copies"),
+            (self.skipped, "skipped - already exist"),
+        ];
+        for (count, what) in others.iter().filter(|(count, _)| *count > 0) {
+            write!(f, ", {} {}", count, what)?;
+        }
+        Ok(())
+    }
+}
+
+/// Merge a backed-up deck into the current one. Cards in both keep whichever
+/// copy was reviewed more recently (the current one on a tie), cards deleted
+/// since the backup come back, and cards added since are kept.
+fn merge_by_last_review(current: Deck, backup: Deck) -> Deck {
+    let mut merged = current;
+    for backed_up in backup.cards {
+        match merged.cards.iter_mut().find(|c| c.id == backed_up.id) {
+            Some(card) if backed_up.last_reviewed > card.last_reviewed => *card = backed_up,
+            Some(_) => {}
+            None => merged.cards.push(backed_up),
+        }
+    }
+    merged.last_studied = merged.last_studied.max(backup.last_studied);
+    merged
+}
+
+/// Write decks to a backup file with `files`, returning the number of decks.
+fn write_backup(decks: &[Deck], path: &Path, files: &FileCodec) -> Result<usize> {
+    let decks = decks
+        .iter()
+        .map(schema::deck_to_value)
+        .collect::<Result<Vec<_>>>()?;
+
+    let backup = Backup {
+        version: BACKUP_VERSION,
+        created_at: chrono::Local::now(),
+        decks,
+    };
+
+    let json = serde_json::to_string_pretty(&backup)?;
+    files.write(path, &json)?;
+
+    Ok(backup.decks.len())
+}
+
+/// Version of the backup format written by this build.
+/// Version 1 backups hold unversioned decks; from version 2 each deck carries
+/// its own `schema_version`.
+pub const BACKUP_VERSION: u32 = 2;
+
+/// Backup format containing all decks.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    pub created_at: chrono::DateTime<chrono::Local>,
+    /// Decks as stored on disk, migrated on import.
+    pub decks: Vec<serde_json::Value>,
+}
+
+impl DeckStorage {
+    /// Export all decks to a backup file, encrypted if the decks are.
+    pub fn export_backup(&self, path: &Path) -> Result<usize> {
+        self.export_decks_backup(&self.load_decks(None)?, path)
+    }
+
+    /// Export the given decks to a backup file, encrypted if the decks are.
+    pub fn export_decks_backup(&self, decks: &[Deck], path: &Path) -> Result<usize> {
+        write_backup(decks, path, &self.files)
+    }
+
+    /// Export the given decks to a backup file encrypted with `passphrase`.
+    pub fn export_encrypted_backup(&self, decks: &[Deck], path: &Path, passphrase: &str) -> Result<usize> {
+        write_backup(decks, path, &FileCodec::encrypted(Cipher::new(passphrase)?))
+    }
+
+    /// Import decks from a backup file.
+    /// `mode` decides what happens to decks that already exist. Encrypted
+    /// backups are decrypted with `passphrase`, or else the decks' own; fails
+    /// with `Locked` if that doesn't fit. A dry run reports what the import
+    /// would do without saving anything.
+    pub fn import_backup(
+        &self,
+        path: &Path,
+        mode: RestoreMode,
+        passphrase: Option<&str>,
+        dry_run: bool,
+    ) -> Result<RestoreReport> {
+        let json = match passphrase {
+            Some(passphrase) => FileCodec::encrypted(Cipher::new(passphrase)?).read(path)?,
+            None => self.files.read(path)?,
+        };
+        let backup: Backup = serde_json::from_str(&json)?;
+        if backup.version > BACKUP_VERSION {
+            anyhow::bail!(
+                "Backup {:?} was written by a newer version of srl (backup format v{}, this build supports up to v{})",
+                path,
+                backup.version,
+                BACKUP_VERSION
+            );
+        }
+        let mut decks = backup
+            .decks
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                schema::deck_from_value(value)
+                    .with_context(|| format!("Failed to read deck {} of backup {:?}", i + 1, path))
+            })
+            .collect::<Result<Vec<Deck>>>()?;
+
+        // A deck ID appearing twice in one backup (e.g. after editing it by
+        // hand) would make the second copy replace the first; restore it as a
+        // new deck. IDs that aren't safe file names get new ones too.
+        let mut ids = std::collections::HashSet::new();
+        for deck in &mut decks {
+            if !is_valid_id(&deck.id) || !ids.insert(deck.id.clone()) {
+                deck.id = crate::models::new_id();
+            }
+            for card in deck.cards.iter_mut().filter(|c| !is_valid_id(&c.id)) {
+                card.id = crate::models::new_id();
+            }
+        }
+
+        let mut report = RestoreReport {
+            dry_run,
+            ..Default::default()
+        };
+        for mut deck in decks {
+            let Some(current) = self.load_deck(&deck.id)? else {
+                if !dry_run {
+                    self.save_deck(&deck)?;
+                }
+                report.imported += 1;
+                continue;
+            };
+
+            match mode {
+                RestoreMode::Skip => report.skipped += 1,
+                RestoreMode::Overwrite => {
+                    if !dry_run {
+                        self.overwrite_deck(&deck)?;
+                    }
+                    report.replaced += 1;
+                }
+                RestoreMode::Merge => {
+                    if !dry_run {
+                        self.save_deck(&merge_by_last_review(current, deck))?;
+                    }
+                    report.merged += 1;
+                }
+                RestoreMode::Rename => {
+                    let restored_at = backup.created_at.format("%Y-%m-%d");
+                    deck.id = crate::models::new_id();
+                    deck.name = format!("{} (restored {})", deck.name, restored_at);
+                    if !dry_run {
+                        self.save_deck(&deck)?;
+                    }
+                    report.renamed += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Get default backup path.
+    pub fn default_backup_path() -> PathBuf {
+        Self::default_export_path("srl_backup", "json")
+    }
+
+    /// Timestamped path for an export, in the documents (or home) directory.
+    pub fn default_export_path(prefix: &str, extension: &str) -> PathBuf {
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        dirs::document_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(format!("{}_{}.{}", prefix, timestamp, extension))
+    }
+}
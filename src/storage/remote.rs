@@ -0,0 +1,320 @@
+//! Syncing with an srl sync server (see `sync::protocol`).
+//!
+//! The decks as of the last sync are kept in `.srl-sync-state` next to the
+//! decks, along with the server USN they correspond to. A sync pulls the
+//! changes made since, applies them to that copy to get the server's current
+//! state, merges each deck three ways against the local one, saves the
+//! result locally and pushes the entities that differ from the server's.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use super::crypto::FileCodec;
+use super::{schema, DeckStorage};
+use crate::models::Deck;
+use crate::sync::protocol::{Change, EntityKind};
+use crate::sync::{merge_decks, ContentConflict, PushRejected, SyncClient};
+
+/// Sync state file inside the decks directory.
+pub const SYNC_STATE_FILE: &str = ".srl-sync-state";
+
+/// Pushes rejected because other clients pushed in between are retried this
+/// many times.
+const MAX_ATTEMPTS: usize = 3;
+
+/// What was synced last, and where to.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    server: String,
+    collection: String,
+    /// Server USN the decks below correspond to.
+    usn: u64,
+    /// Decks as of the last sync, as stored on disk.
+    decks: BTreeMap<String, Value>,
+}
+
+impl SyncState {
+    /// Load the state for `client`'s collection. Switching servers or
+    /// collections starts over, as if never synced.
+    fn load(path: &Path, client: &SyncClient, files: &FileCodec) -> Result<Self> {
+        let state = match files.read(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse sync state {:?}", path))?,
+            Err(_) => Self::default(),
+        };
+        if state.server == client.server() && state.collection == client.collection() {
+            return Ok(state);
+        }
+        Ok(Self {
+            server: client.server().to_string(),
+            collection: client.collection().to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn save(&self, path: &Path, files: &FileCodec) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        files
+            .write(&tmp, &serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to write sync state {:?}", tmp))?;
+        fs::rename(&tmp, path).with_context(|| format!("Failed to write sync state {:?}", path))?;
+        Ok(())
+    }
+
+    fn decks(&self) -> Result<BTreeMap<String, Deck>> {
+        self.decks
+            .iter()
+            .map(|(id, value)| Ok((id.clone(), schema::deck_from_value(value.clone())?)))
+            .collect()
+    }
+}
+
+/// What a server sync did.
+#[derive(Debug, Default)]
+pub struct ServerSyncReport {
+    pub collection: String,
+    /// Changes received from the server.
+    pub pulled: usize,
+    /// Changes sent to the server.
+    pub pushed: usize,
+    /// Local decks added, changed, or removed by the sync.
+    pub decks_updated: usize,
+    pub conflicts: Vec<ContentConflict>,
+}
+
+impl fmt::Display for ServerSyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.pulled > 0 {
+            parts.push(format!("pulled {} changes", self.pulled));
+        }
+        if self.decks_updated > 0 {
+            parts.push(format!("updated {} decks", self.decks_updated));
+        }
+        if self.pushed > 0 {
+            parts.push(format!("pushed {} changes", self.pushed));
+        }
+        if !self.conflicts.is_empty() {
+            parts.push(format!("{} cards edited on both sides", self.conflicts.len()));
+        }
+
+        if parts.is_empty() {
+            write!(f, "Sync: '{}' already up to date", self.collection)
+        } else {
+            write!(f, "Sync with '{}': {}", self.collection, parts.join(", "))
+        }
+    }
+}
+
+/// A deck split into the entities the server stores, keyed by `Change::key`.
+fn entities(deck: &Deck) -> Result<BTreeMap<String, Change>> {
+    let mut meta = schema::deck_to_value(deck)?;
+    if let Some(obj) = meta.as_object_mut() {
+        obj.remove("cards");
+        let order: Vec<&str> = deck.cards.iter().map(|c| c.id.as_str()).collect();
+        obj.insert("card_order".to_string(), serde_json::to_value(order)?);
+    }
+
+    let mut changes = vec![Change {
+        kind: EntityKind::Deck,
+        deck_id: deck.id.clone(),
+        id: deck.id.clone(),
+        usn: 0,
+        data: Some(meta),
+    }];
+    for card in &deck.cards {
+        changes.push(Change {
+            kind: EntityKind::Card,
+            deck_id: deck.id.clone(),
+            id: card.id.clone(),
+            usn: 0,
+            data: Some(serde_json::to_value(card)?),
+        });
+    }
+    Ok(changes.into_iter().map(|c| (c.key(), c)).collect())
+}
+
+/// The server's decks: the last synced copy with the pulled changes applied.
+fn apply_changes(base: &BTreeMap<String, Deck>, changes: &[Change]) -> Result<BTreeMap<String, Deck>> {
+    // IDs name files here, and leaving a bad change out would delete its
+    // entity instead, so refuse the lot
+    if let Some(change) = changes.iter().find(|c| !c.is_well_formed()) {
+        anyhow::bail!("The sync server sent {} with an invalid ID", change.key());
+    }
+
+    let mut all: BTreeMap<String, Change> = BTreeMap::new();
+    for deck in base.values() {
+        all.extend(entities(deck)?);
+    }
+    for change in changes {
+        all.insert(change.key(), change.clone());
+    }
+
+    let mut metas: BTreeMap<String, Value> = BTreeMap::new();
+    let mut cards: BTreeMap<String, BTreeMap<String, Value>> = BTreeMap::new();
+    for change in all.into_values() {
+        let Some(data) = change.data else {
+            continue;
+        };
+        match change.kind {
+            EntityKind::Deck => {
+                metas.insert(change.deck_id, data);
+            }
+            EntityKind::Card => {
+                cards.entry(change.deck_id).or_default().insert(change.id, data);
+            }
+        }
+    }
+
+    let mut decks = BTreeMap::new();
+    for (deck_id, mut meta) in metas {
+        let mut deck_cards = cards.remove(&deck_id).unwrap_or_default();
+        let order: Vec<String> = meta
+            .get("card_order")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+
+        // Cards missing from the order were added by a push that didn't
+        // touch the deck entity; they go last
+        let mut ordered: Vec<Value> = order.iter().filter_map(|id| deck_cards.remove(id)).collect();
+        ordered.extend(deck_cards.into_values());
+
+        if let Some(obj) = meta.as_object_mut() {
+            obj.remove("card_order");
+            obj.insert("cards".to_string(), Value::Array(ordered));
+        }
+        let deck = schema::deck_from_value(meta)
+            .with_context(|| format!("Failed to read deck {} from the sync server", deck_id))?;
+        decks.insert(deck_id, deck);
+    }
+    Ok(decks)
+}
+
+/// Entities to push to turn the server's copy of a deck into `merged`.
+fn diff(remote: Option<&Deck>, merged: Option<&Deck>) -> Result<Vec<Change>> {
+    let old = remote.map(entities).transpose()?.unwrap_or_default();
+    let new = merged.map(entities).transpose()?.unwrap_or_default();
+
+    let mut changes = Vec::new();
+    for (key, change) in &old {
+        if !new.contains_key(key) {
+            changes.push(Change { data: None, ..change.clone() });
+        }
+    }
+    for (key, change) in new {
+        if old.get(&key).map(|c| &c.data) != Some(&change.data) {
+            changes.push(change);
+        }
+    }
+    Ok(changes)
+}
+
+impl DeckStorage {
+    /// Two-way sync with a collection on an srl sync server.
+    pub fn sync_with_server(&self, client: &SyncClient) -> Result<ServerSyncReport> {
+        let dir = self
+            .dir()
+            .context("Server sync needs decks stored in a directory")?;
+        let state_path = dir.join(SYNC_STATE_FILE);
+        let mut state = SyncState::load(&state_path, client, &self.files)?;
+
+        for _ in 0..MAX_ATTEMPTS {
+            let mut report = ServerSyncReport {
+                collection: client.collection().to_string(),
+                ..Default::default()
+            };
+
+            // An unreadable deck would look deleted and be deleted everywhere
+            let (infos, broken) = self.scan_decks()?;
+            if !broken.is_empty() {
+                anyhow::bail!(
+                    "{} deck files can't be read; repair or quarantine them before syncing",
+                    broken.len()
+                );
+            }
+            let mut local = BTreeMap::new();
+            for info in infos {
+                if let Some(deck) = self.load_deck(&info.id)? {
+                    local.insert(info.id, deck);
+                }
+            }
+
+            let pulled = client.pull(state.usn)?;
+            report.pulled = pulled.changes.len();
+            let base = state.decks()?;
+            let remote = apply_changes(&base, &pulled.changes)?;
+
+            let ids: BTreeSet<&String> = base.keys().chain(local.keys()).chain(remote.keys()).collect();
+            let mut synced = BTreeMap::new();
+            let mut changes = Vec::new();
+
+            for id in ids {
+                let (base, local, remote) = (base.get(id), local.get(id), remote.get(id));
+                let merged = match (base, local, remote) {
+                    (_, Some(l), Some(r)) if l == r => Some(l.clone()),
+                    (Some(b), Some(l), Some(r)) if l == b => Some(r.clone()),
+                    (Some(b), Some(l), Some(r)) if r == b => Some(l.clone()),
+                    (b, Some(l), Some(r)) => Some(merge_decks(b, l, r, &mut report.conflicts)),
+                    // Deleted on the server; keep only if changed here
+                    (Some(b), Some(l), None) if l == b => None,
+                    (_, Some(l), None) => Some(l.clone()),
+                    // Deleted here; keep only if changed on the server
+                    (Some(b), None, Some(r)) if r == b => None,
+                    (_, None, Some(r)) => Some(r.clone()),
+                    (_, None, None) => None,
+                };
+
+                if merged.as_ref() != local {
+                    match merged {
+                        Some(ref deck) => self.save_deck(deck)?,
+                        None => {
+                            self.delete_deck(id)?;
+                        }
+                    }
+                    report.decks_updated += 1;
+                }
+                changes.extend(diff(remote, merged.as_ref())?);
+                if let Some(deck) = merged {
+                    synced.insert(id.clone(), schema::deck_to_value(&deck)?);
+                }
+            }
+
+            report.pushed = changes.len();
+            let usn = if changes.is_empty() {
+                pulled.usn
+            } else {
+                match client.push(pulled.usn, changes) {
+                    Ok(response) => response.usn,
+                    // Someone pushed in between; merge their changes too
+                    Err(e) if e.is::<PushRejected>() => continue,
+                    Err(e) => return Err(e),
+                }
+            };
+
+            state.usn = usn;
+            state.decks = synced;
+            state.save(&state_path, &self.files)?;
+            return Ok(report);
+        }
+
+        anyhow::bail!("Other clients kept pushing to '{}' during the sync; try again", client.collection())
+    }
+
+    /// Write the sync state in `dir`, written with `old`, to the same place
+    /// under `to` with `new`.
+    pub(super) fn reencode_sync_state(dir: &Path, to: &Path, old: &FileCodec, new: &FileCodec) -> Result<()> {
+        let path = dir.join(SYNC_STATE_FILE);
+        if !path.exists() {
+            return Ok(());
+        }
+        let json = old.read(&path)?;
+        let target = to.join(SYNC_STATE_FILE);
+        new.write(&target, &json)
+            .with_context(|| format!("Failed to write sync state {:?}", target))
+    }
+}
@@ -0,0 +1,193 @@
+//! Versioned on-disk deck format and migrations between versions.
+//!
+//! Every stored deck carries a `schema_version` field. Decks are upgraded to
+//! `CURRENT_VERSION` on load by running each migration step in order, and
+//! decks written by a newer srl are refused instead of being loaded with their
+//! unknown fields silently dropped on the next save.
+//!
+//! To change the format: bump `CURRENT_VERSION` and append a `Migration`
+//! whose `from` is the previous version.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::models::Deck;
+
+/// Version of the deck format written by this build.
+pub const CURRENT_VERSION: u32 = 4;
+
+/// Field holding the format version in stored deck JSON.
+pub const VERSION_FIELD: &str = "schema_version";
+
+/// Version assumed for decks saved before versioning existed.
+const UNVERSIONED: u32 = 1;
+
+/// A step upgrading deck JSON from version `from` to `from + 1`.
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: fn(&mut Value),
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        description: "reset ease factors below the 1.3 minimum (left at 0 by older Anki imports)",
+        apply: migrate_v1_ease_factors,
+    },
+    Migration {
+        from: 2,
+        description: "start a review log for each card (earlier reviews only count towards totals)",
+        apply: migrate_v2_review_log,
+    },
+    Migration {
+        from: 3,
+        description: "allow suspended and buried cards (nothing to convert)",
+        apply: migrate_v3_card_states,
+    },
+];
+
+/// Version 1 -> 2: older Anki imports stored Anki's `factor` of 0 for new
+/// cards as an ease of 0.0, which SM-2 can't schedule from.
+fn migrate_v1_ease_factors(deck: &mut Value) {
+    let Some(cards) = deck.get_mut("cards").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    for card in cards {
+        let ease = card.get("ease_factor").and_then(Value::as_f64).unwrap_or(2.5);
+        if ease >= 1.3 {
+            continue;
+        }
+        let is_new = card.get("repetitions").and_then(Value::as_u64).unwrap_or(0) == 0;
+        card["ease_factor"] = Value::from(if is_new { 2.5 } else { 1.3 });
+    }
+}
+
+/// Version 2 -> 3: cards keep a `reviews` log. Older builds would drop it
+/// on save, hence the version bump; existing cards start with an empty log.
+fn migrate_v2_review_log(deck: &mut Value) {
+    let Some(cards) = deck.get_mut("cards").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    for card in cards.iter_mut().filter_map(Value::as_object_mut) {
+        card.entry("reviews").or_insert_with(|| Value::Array(Vec::new()));
+    }
+}
+
+/// Version 3 -> 4: cards can be `suspended` or `buried_until` a time.
+/// Existing cards are neither; the bump keeps older builds, which would drop
+/// both fields on save, from opening such decks.
+fn migrate_v3_card_states(_deck: &mut Value) {}
+
+/// Format version of stored deck JSON.
+pub fn version_of(deck: &Value) -> u32 {
+    deck.get(VERSION_FIELD)
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(UNVERSIONED)
+}
+
+/// Descriptions of the migration steps a deck at `version` needs.
+/// Fails if the deck was written by a newer srl.
+pub fn pending_migrations(version: u32) -> Result<Vec<&'static str>> {
+    if version > CURRENT_VERSION {
+        anyhow::bail!(
+            "written by a newer version of srl (deck format v{}, this build supports up to v{}); \
+             upgrade srl to open it",
+            version,
+            CURRENT_VERSION
+        );
+    }
+
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|m| m.from >= version)
+        .map(|m| m.description)
+        .collect())
+}
+
+/// Upgrade deck JSON to the current version in place.
+/// Returns the descriptions of the steps that ran.
+pub fn migrate(deck: &mut Value) -> Result<Vec<&'static str>> {
+    let version = version_of(deck);
+    let steps = pending_migrations(version)?;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= version) {
+        (migration.apply)(deck);
+    }
+    if let Some(obj) = deck.as_object_mut() {
+        obj.insert(VERSION_FIELD.to_string(), Value::from(CURRENT_VERSION));
+    }
+
+    Ok(steps)
+}
+
+/// Migrate stored deck JSON and deserialize it.
+/// Cards sharing an ID are given their own (see `Deck::dedupe_card_ids`).
+pub fn deck_from_value(mut value: Value) -> Result<Deck> {
+    migrate(&mut value)?;
+    let mut deck: Deck = serde_json::from_value(value)?;
+    deck.dedupe_card_ids();
+    Ok(deck)
+}
+
+/// Number of cards in stored deck JSON whose ID an earlier card already has.
+fn duplicate_card_ids(deck: &Value) -> usize {
+    let Some(cards) = deck.get("cards").and_then(Value::as_array) else {
+        return 0;
+    };
+    let mut seen = std::collections::HashSet::new();
+    cards
+        .iter()
+        .filter_map(|c| c.get("id").and_then(Value::as_str))
+        .filter(|id| !seen.insert(*id))
+        .count()
+}
+
+/// Parse a stored deck, migrating it to the current version.
+pub fn parse_deck(json: &str) -> Result<Deck> {
+    let value: Value = serde_json::from_str(json)?;
+    deck_from_value(value)
+}
+
+/// Deck JSON as stored on disk, tagged with the current version.
+pub fn deck_to_value(deck: &Deck) -> Result<Value> {
+    let mut value = serde_json::to_value(deck).with_context(|| "Failed to serialize deck")?;
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert(VERSION_FIELD.to_string(), Value::from(CURRENT_VERSION));
+    }
+    Ok(value)
+}
+
+/// Migration state of one stored deck, for `--migrate` reports.
+#[derive(Debug, Clone)]
+pub struct SchemaStatus {
+    /// File name or deck ID.
+    pub label: String,
+    pub deck_id: Option<String>,
+    pub version: u32,
+    /// Steps that would run, or the reason the deck can't be migrated.
+    pub pending: Result<Vec<&'static str>, String>,
+    /// Cards that share their ID with another card and get a new one on load.
+    pub duplicate_card_ids: usize,
+}
+
+impl SchemaStatus {
+    pub fn of(label: String, deck: &Value) -> Self {
+        let version = version_of(deck);
+        Self {
+            label,
+            deck_id: deck.get("id").and_then(Value::as_str).map(str::to_string),
+            version,
+            pending: pending_migrations(version).map_err(|e| e.to_string()),
+            duplicate_card_ids: duplicate_card_ids(deck),
+        }
+    }
+
+    /// Whether saving the deck again would change what is stored.
+    pub fn needs_migration(&self) -> bool {
+        self.version < CURRENT_VERSION || self.duplicate_card_ids > 0
+    }
+}
@@ -0,0 +1,271 @@
+//! SQLite backend: all decks in a single database with per-card rows.
+//!
+//! Each card is stored as its JSON representation alongside the columns needed
+//! for indexed queries (position, repetitions, due time, suspended or buried),
+//! so reviewing a card
+//! updates one row instead of rewriting the whole deck. Each deck row counts
+//! the saves to it, which is all a conflict check needs to read.
+
+use anyhow::{Context, Result};
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+use super::schema::{self, SchemaStatus};
+use super::{BrokenDeck, DeckInfo, DeckRepository, DeckVersion};
+use crate::models::{Card, Deck};
+
+/// File name of the database inside the decks directory.
+pub const DATABASE_FILE: &str = "collection.sqlite3";
+
+const SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS decks (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        description TEXT NOT NULL DEFAULT '',
+        data TEXT NOT NULL,
+        version INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE IF NOT EXISTS cards (
+        deck_id TEXT NOT NULL REFERENCES decks(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        id TEXT NOT NULL,
+        repetitions INTEGER NOT NULL,
+        due_at INTEGER,
+        suspended INTEGER NOT NULL DEFAULT 0,
+        buried_until INTEGER,
+        data TEXT NOT NULL,
+        PRIMARY KEY (deck_id, position)
+    );
+    CREATE INDEX IF NOT EXISTS cards_by_id ON cards(deck_id, id);
+    CREATE INDEX IF NOT EXISTS cards_by_due ON cards(deck_id, repetitions, due_at);
+"#;
+
+/// Stores every deck in one SQLite database.
+pub struct SqliteRepository {
+    conn: Connection,
+}
+
+impl SqliteRepository {
+    /// Open (or create) the database at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open SQLite database: {:?}", path))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+        conn.execute_batch(SCHEMA)
+            .with_context(|| "Failed to create SQLite schema")?;
+        // Databases created before decks were versioned
+        if conn.prepare("SELECT version FROM decks LIMIT 0").is_err() {
+            conn.execute("ALTER TABLE decks ADD COLUMN version INTEGER NOT NULL DEFAULT 0", [])
+                .with_context(|| "Failed to add deck versions to the SQLite schema")?;
+        }
+        // Databases created before cards could be suspended or buried
+        if conn.prepare("SELECT suspended FROM cards LIMIT 0").is_err() {
+            Self::add_hold_columns(&conn).with_context(|| "Failed to add card holds to the SQLite schema")?;
+        }
+
+        Ok(Self { conn })
+    }
+
+    /// Add the `suspended` and `buried_until` columns, filled in from each
+    /// card's JSON.
+    fn add_hold_columns(conn: &Connection) -> Result<()> {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(
+            "ALTER TABLE cards ADD COLUMN suspended INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE cards ADD COLUMN buried_until INTEGER;",
+        )?;
+        let rows: Vec<(i64, String)> = tx
+            .prepare("SELECT rowid, data FROM cards")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        for (rowid, data) in rows {
+            let card: serde_json::Value = serde_json::from_str(&data)?;
+            let suspended = card.get("suspended").and_then(|v| v.as_bool()).unwrap_or(false);
+            let buried_until = card
+                .get("buried_until")
+                .and_then(|v| v.as_str())
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                .map(|d| d.timestamp());
+            tx.execute(
+                "UPDATE cards SET suspended = ?1, buried_until = ?2 WHERE rowid = ?3",
+                params![suspended, buried_until, rowid],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Deck metadata as JSON, without its cards.
+    fn deck_data(deck: &Deck) -> Result<String> {
+        let mut value = schema::deck_to_value(deck)?;
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("cards".to_string(), serde_json::Value::Array(Vec::new()));
+        }
+        Ok(value.to_string())
+    }
+
+    fn insert_card(tx: &rusqlite::Transaction, deck_id: &str, position: usize, card: &Card) -> Result<()> {
+        tx.execute(
+            "INSERT INTO cards (deck_id, position, id, repetitions, due_at, suspended, buried_until, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                deck_id,
+                position as i64,
+                &card.id,
+                card.repetitions as i64,
+                card.due_date.map(|d| d.timestamp()),
+                card.suspended,
+                card.buried_until.map(|d| d.timestamp()),
+                serde_json::to_string(card)?,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl DeckRepository for SqliteRepository {
+    fn load_deck(&self, deck_id: &str) -> Result<Option<Deck>> {
+        let data: Option<String> = self
+            .conn
+            .query_row("SELECT data FROM decks WHERE id = ?1", [deck_id], |row| row.get(0))
+            .optional()?;
+        let Some(data) = data else {
+            return Ok(None);
+        };
+
+        let mut value: serde_json::Value = serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse deck {}", deck_id))?;
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT data FROM cards WHERE deck_id = ?1 ORDER BY position")?;
+        let rows = stmt.query_map([deck_id], |row| row.get::<_, String>(0))?;
+        let mut cards = Vec::new();
+        for row in rows {
+            let card: serde_json::Value = serde_json::from_str(&row?)
+                .with_context(|| format!("Failed to parse card in deck {}", deck_id))?;
+            cards.push(card);
+        }
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("cards".to_string(), serde_json::Value::Array(cards));
+        }
+
+        // Migrations see the whole deck, as they would for a JSON file
+        let deck = schema::deck_from_value(value)
+            .with_context(|| format!("Failed to load deck {}", deck_id))?;
+        Ok(Some(deck))
+    }
+
+    fn save_deck(&self, deck: &Deck) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        tx.execute(
+            "INSERT INTO decks (id, name, description, data) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                description = excluded.description,
+                data = excluded.data,
+                version = version + 1",
+            params![&deck.id, &deck.name, &deck.description, Self::deck_data(deck)?],
+        )?;
+        tx.execute("DELETE FROM cards WHERE deck_id = ?1", [&deck.id])?;
+        for (position, card) in deck.cards.iter().enumerate() {
+            Self::insert_card(&tx, &deck.id, position, card)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_card(&self, deck: &Deck, card_idx: usize) -> Result<()> {
+        let Some(card) = deck.cards.get(card_idx) else {
+            return self.save_deck(deck);
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let updated = tx.execute(
+            "UPDATE cards SET repetitions = ?1, due_at = ?2, suspended = ?3, buried_until = ?4, data = ?5
+             WHERE deck_id = ?6 AND position = ?7 AND id = ?8",
+            params![
+                card.repetitions as i64,
+                card.due_date.map(|d| d.timestamp()),
+                card.suspended,
+                card.buried_until.map(|d| d.timestamp()),
+                serde_json::to_string(card)?,
+                &deck.id,
+                card_idx as i64,
+                &card.id,
+            ],
+        )?;
+
+        // The stored deck no longer lines up with the one in memory
+        if updated == 0 {
+            drop(tx);
+            return self.save_deck(deck);
+        }
+        tx.execute("UPDATE decks SET version = version + 1 WHERE id = ?1", [&deck.id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn version(&self, deck_id: &str) -> Result<Option<DeckVersion>> {
+        let version: Option<i64> = self
+            .conn
+            .query_row("SELECT version FROM decks WHERE id = ?1", [deck_id], |row| row.get(0))
+            .optional()?;
+        Ok(version.map(DeckVersion::of))
+    }
+
+    fn delete_deck(&self, deck_id: &str) -> Result<bool> {
+        let deleted = self.conn.execute("DELETE FROM decks WHERE id = ?1", [deck_id])?;
+        Ok(deleted > 0)
+    }
+
+    fn scan_decks(&self) -> Result<(Vec<DeckInfo>, Vec<BrokenDeck>)> {
+        let now = Local::now().timestamp();
+        let mut stmt = self.conn.prepare(
+            "SELECT d.id, d.name, d.description,
+                    COUNT(c.position),
+                    COALESCE(SUM(c.repetitions = 0 AND NOT c.suspended
+                        AND (c.buried_until IS NULL OR c.buried_until <= ?1)), 0),
+                    COALESCE(SUM(c.repetitions > 0 AND (c.due_at IS NULL OR c.due_at <= ?1) AND NOT c.suspended
+                        AND (c.buried_until IS NULL OR c.buried_until <= ?1)), 0)
+             FROM decks d
+             LEFT JOIN cards c ON c.deck_id = d.id
+             GROUP BY d.id
+             ORDER BY d.name",
+        )?;
+
+        let decks = stmt
+            .query_map([now], |row| {
+                Ok(DeckInfo {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    card_count: row.get::<_, i64>(3)? as usize,
+                    new_count: row.get::<_, i64>(4)? as usize,
+                    due_count: row.get::<_, i64>(5)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        // Rows are written by srl itself, so there are no unreadable files to report
+        Ok((decks, Vec::new()))
+    }
+
+    fn schema_report(&self) -> Result<Vec<SchemaStatus>> {
+        let mut stmt = self.conn.prepare("SELECT id, data FROM decks ORDER BY id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+
+        let mut report = Vec::new();
+        for row in rows {
+            let (id, data) = row?;
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(&data) {
+                report.push(SchemaStatus::of(id, &value));
+            }
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,84 @@
+//! Notices deck files added, removed, or edited by other programs.
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use super::conflict::LOCK_FILE;
+use super::crypto::KEY_FILE;
+use super::index::INDEX_FILE;
+use super::media::MEDIA_DIR;
+use super::remote::SYNC_STATE_FILE;
+
+/// Bursts of events closer together than this are reported as one change,
+/// so a script rewriting many files triggers a single reload.
+const SETTLE_TIME: Duration = Duration::from_millis(250);
+
+/// Watches a decks directory for changes.
+pub struct DeckWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    /// Media folders, as given and canonicalized, since platforms differ in
+    /// which form events use.
+    media_dirs: [PathBuf; 2],
+    pending_since: Option<Instant>,
+}
+
+impl DeckWatcher {
+    pub fn new(dir: &Path) -> Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)
+            .with_context(|| "Failed to start file watcher")?;
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {:?}", dir))?;
+
+        let media_dir = dir.join(MEDIA_DIR);
+        let canonical = dir.canonicalize().map_or_else(|_| media_dir.clone(), |d| d.join(MEDIA_DIR));
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            media_dirs: [media_dir, canonical],
+            pending_since: None,
+        })
+    }
+
+    /// Whether the directory changed since the last call that returned true.
+    /// Never blocks; call it regularly from the UI loop.
+    pub fn poll(&mut self) -> bool {
+        while let Ok(event) = self.events.try_recv() {
+            // Watcher errors are treated as a change; rescanning is harmless
+            let relevant = event.map_or(true, |e| is_deck_change(&e, &self.media_dirs));
+            if relevant {
+                self.pending_since = Some(Instant::now());
+            }
+        }
+
+        match self.pending_since {
+            Some(since) if since.elapsed() >= SETTLE_TIME => {
+                self.pending_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Ignore reads, srl's own bookkeeping files and media.
+fn is_deck_change(event: &notify::Event, media_dirs: &[PathBuf]) -> bool {
+    let modifies = matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) | EventKind::Any
+    );
+    modifies
+        && event.paths.iter().any(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            name != LOCK_FILE
+                && name != INDEX_FILE
+                && name != KEY_FILE
+                && !name.starts_with(SYNC_STATE_FILE)
+                && !media_dirs.iter().any(|dir| path.starts_with(dir))
+        })
+}
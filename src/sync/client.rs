@@ -0,0 +1,114 @@
+//! HTTP client for an srl sync server.
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::time::Duration;
+
+use super::protocol::{
+    valid_collection_name, Change, ChangesResponse, PushRequest, PushResponse, PROTOCOL_VERSION, VERSION_HEADER,
+};
+use crate::config::SyncConfig;
+
+/// The server has changes the push wasn't merged with. Pull and try again.
+#[derive(Debug)]
+pub struct PushRejected;
+
+impl fmt::Display for PushRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The server has changes that were not merged yet")
+    }
+}
+
+impl std::error::Error for PushRejected {}
+
+/// Talks to one collection on a sync server.
+pub struct SyncClient {
+    server: String,
+    collection: String,
+    token: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl SyncClient {
+    /// Client for the server configured in `[sync]`.
+    pub fn new(config: &SyncConfig) -> Result<Self> {
+        let server = config
+            .server
+            .as_deref()
+            .context("No sync server configured (set `server` under [sync] in config.toml)")?;
+        if !valid_collection_name(&config.collection) {
+            anyhow::bail!(
+                "Invalid sync collection name {:?} (use letters, digits, '-' and '_')",
+                config.collection
+            );
+        }
+
+        Ok(Self {
+            server: server.trim_end_matches('/').to_string(),
+            collection: config.collection.clone(),
+            token: config.token.clone(),
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build(),
+        })
+    }
+
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = self
+            .agent
+            .request(method, url)
+            .set(VERSION_HEADER, &PROTOCOL_VERSION.to_string());
+        match self.token {
+            Some(ref token) => request.set("Authorization", &format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    fn changes_url(&self) -> String {
+        format!("{}/v1/collections/{}/changes", self.server, self.collection)
+    }
+
+    /// Everything changed on the server since `since`.
+    pub fn pull(&self, since: u64) -> Result<ChangesResponse> {
+        let response = self
+            .request("GET", &self.changes_url())
+            .query("since", &since.to_string())
+            .call()
+            .map_err(|e| self.error(e))?;
+        response
+            .into_json()
+            .with_context(|| format!("Invalid response from sync server {}", self.server))
+    }
+
+    /// Send changes merged against the server's state at `base_usn`.
+    /// Fails with `PushRejected` if the server has moved on since.
+    pub fn push(&self, base_usn: u64, changes: Vec<Change>) -> Result<PushResponse> {
+        let body = PushRequest { base_usn, changes };
+        let response = match self.request("POST", &self.changes_url()).send_json(&body) {
+            Ok(response) => response,
+            Err(ureq::Error::Status(409, _)) => return Err(PushRejected.into()),
+            Err(e) => return Err(self.error(e)),
+        };
+        response
+            .into_json()
+            .with_context(|| format!("Invalid response from sync server {}", self.server))
+    }
+
+    fn error(&self, error: ureq::Error) -> anyhow::Error {
+        match error {
+            ureq::Error::Status(status, response) => {
+                let message = response.into_string().unwrap_or_default();
+                anyhow::anyhow!("Sync server {} returned {}: {}", self.server, status, message.trim())
+            }
+            ureq::Error::Transport(e) => {
+                anyhow::anyhow!("Could not reach sync server {}: {}", self.server, e)
+            }
+        }
+    }
+}
@@ -0,0 +1,494 @@
+//! Sync the decks directory through git.
+//!
+//! A sync commits local deck changes with a summary of what changed, merges
+//! the upstream branch, and pushes. Deck files that conflict are merged card
+//! by card (see `merge`) instead of leaving conflict markers in the JSON.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::merge::{cards_by_id, merge_card, merge_decks, ContentConflict};
+use crate::models::Card;
+use crate::storage::{schema, DeckStorage, StorageBackend};
+
+/// Files srl keeps next to the decks that don't belong in version control.
+const LOCAL_ONLY: &[&str] = &[".deck_index", ".srl.lock", ".srl-sync-state", "quarantine/"];
+
+/// What a sync did.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Subject of the commit made for local changes.
+    pub committed: Option<String>,
+    /// Whether upstream changes were merged in.
+    pub merged: bool,
+    /// Deck files whose git conflicts were resolved card by card.
+    pub resolved_files: usize,
+    /// Remote branch pushed to.
+    pub pushed: Option<String>,
+    pub conflicts: Vec<ContentConflict>,
+}
+
+impl std::fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(ref subject) = self.committed {
+            parts.push(format!("committed \"{}\"", subject));
+        }
+        if self.merged {
+            parts.push("merged remote changes".to_string());
+        }
+        if self.resolved_files > 0 {
+            parts.push(format!("resolved {} conflicting files", self.resolved_files));
+        }
+        if let Some(ref remote) = self.pushed {
+            parts.push(format!("pushed to {}", remote));
+        }
+        if !self.conflicts.is_empty() {
+            parts.push(format!("{} cards edited on both sides", self.conflicts.len()));
+        }
+
+        if parts.is_empty() {
+            write!(f, "Sync: already up to date")
+        } else {
+            write!(f, "Sync: {}", parts.join(", "))
+        }
+    }
+}
+
+/// Syncs a decks directory that is (part of) a git work tree.
+pub struct GitSync {
+    dir: PathBuf,
+    backend: StorageBackend,
+}
+
+impl GitSync {
+    /// Sync the directory `storage` keeps its decks in.
+    pub fn for_storage(storage: &DeckStorage) -> Result<Self> {
+        let backend = storage.backend().unwrap_or_default();
+        if !matches!(backend, StorageBackend::Json | StorageBackend::Directory) {
+            anyhow::bail!("Git sync needs the json or directory storage backend");
+        }
+        if storage.is_encrypted() {
+            anyhow::bail!("Git can't merge encrypted decks; set up a sync server to sync them");
+        }
+        let dir = storage
+            .dir()
+            .context("Git sync needs decks stored in a directory")?
+            .to_path_buf();
+        Ok(Self { dir, backend })
+    }
+
+    /// Commit local changes, merge upstream changes, and push.
+    pub fn sync(&self, storage: &DeckStorage) -> Result<SyncReport> {
+        // Keep other srl processes from saving halfway through
+        let _lock = storage.lock()?;
+
+        self.git(&["rev-parse", "--is-inside-work-tree"]).with_context(|| {
+            format!("{:?} is not a git repository (run `git init` there and add a remote)", self.dir)
+        })?;
+        self.exclude_local_files()?;
+
+        let mut report = SyncReport {
+            committed: self.commit_local_changes()?,
+            ..Default::default()
+        };
+
+        let Some(remote) = self.remote()? else {
+            return Ok(report);
+        };
+        let branch = self.git(&["symbolic-ref", "--short", "HEAD"])?;
+        let remote_ref = format!("{}/{}", remote, branch);
+
+        self.git(&["fetch", "--quiet", &remote])?;
+        if self.git(&["rev-parse", "--verify", "--quiet", &remote_ref]).is_ok() {
+            self.merge(&remote_ref, &mut report)?;
+        }
+
+        let before = self.git(&["rev-parse", "--verify", "--quiet", &remote_ref]).ok();
+        self.git(&["push", "--quiet", "--set-upstream", &remote, &format!("HEAD:{}", branch)])?;
+        if before != self.git(&["rev-parse", "--verify", "--quiet", &remote_ref]).ok() {
+            report.pushed = Some(remote_ref);
+        }
+
+        Ok(report)
+    }
+
+    /// Run git in the decks directory, returning its trimmed stdout.
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null())
+            .output()
+            .context("Failed to run git (is it installed?)")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.first().copied().unwrap_or_default(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Run a git command that creates commits, with a fallback identity
+    /// when git has none configured.
+    fn git_committing(&self, args: &[&str]) -> Result<String> {
+        let mut full: Vec<&str> = Vec::new();
+        if self.git(&["config", "user.email"]).is_err() {
+            full.extend(["-c", "user.name=srl", "-c", "user.email=srl@localhost"]);
+        }
+        full.extend(args);
+        self.git(&full)
+    }
+
+    /// The remote to sync with: the current branch's, else `origin`, else the first.
+    fn remote(&self) -> Result<Option<String>> {
+        if let Ok(upstream) = self.git(&["rev-parse", "--abbrev-ref", "@{upstream}"]) {
+            if let Some((remote, _)) = upstream.split_once('/') {
+                return Ok(Some(remote.to_string()));
+            }
+        }
+        let remotes = self.git(&["remote"])?;
+        let remotes: Vec<&str> = remotes.lines().collect();
+        let remote = remotes
+            .iter()
+            .find(|r| **r == "origin")
+            .or(remotes.first())
+            .map(|r| r.to_string());
+        Ok(remote)
+    }
+
+    /// Keep srl's cache and lock files out of commits without touching the
+    /// repository's own `.gitignore`.
+    fn exclude_local_files(&self) -> Result<()> {
+        let exclude = PathBuf::from(self.git(&["rev-parse", "--git-path", "info/exclude"])?);
+        let exclude = if exclude.is_absolute() { exclude } else { self.dir.join(exclude) };
+        let mut contents = fs::read_to_string(&exclude).unwrap_or_default();
+
+        let missing: Vec<&str> = LOCAL_ONLY
+            .iter()
+            .copied()
+            .filter(|entry| !contents.lines().any(|line| line == *entry))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        for entry in missing {
+            contents.push_str(entry);
+            contents.push('\n');
+        }
+        if let Some(parent) = exclude.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&exclude, contents).with_context(|| format!("Failed to write {:?}", exclude))?;
+        Ok(())
+    }
+
+    /// Commit changed deck files, returning the commit subject.
+    fn commit_local_changes(&self) -> Result<Option<String>> {
+        self.git(&["add", "--all", "--", "."])?;
+        let changed = self.git(&["diff", "--cached", "--name-status", "--no-renames", "--relative", "--", "."])?;
+        if changed.is_empty() {
+            return Ok(None);
+        }
+
+        let (subject, body) = self.describe_changes(&changed);
+        let message = if body.is_empty() { subject.clone() } else { format!("{}\n\n{}", subject, body) };
+        self.git_committing(&["commit", "--quiet", "-m", &message, "--", "."])?;
+        Ok(Some(subject))
+    }
+
+    /// Commit message summarizing reviews and edits per deck, from
+    /// `git diff --name-status` output.
+    fn describe_changes(&self, name_status: &str) -> (String, String) {
+        let mut decks: BTreeMap<String, DeckChanges> = BTreeMap::new();
+
+        for line in name_status.lines() {
+            let Some((status, path)) = line.split_once('\t') else {
+                continue;
+            };
+            let Some(file) = DeckFile::classify(self.backend, path) else {
+                continue;
+            };
+            let old = self.git(&["show", &format!("HEAD:./{}", path)]).ok();
+            let new = if status == "D" { None } else { fs::read_to_string(self.dir.join(path)).ok() };
+            let changes = decks.entry(file.deck_id().to_string()).or_default();
+
+            match file {
+                DeckFile::Json { .. } => {
+                    let old = old.and_then(|json| schema::parse_deck(&json).ok());
+                    let new = new.and_then(|json| schema::parse_deck(&json).ok());
+                    changes.deck_added |= old.is_none() && new.is_some();
+                    changes.deck_removed |= old.is_some() && new.is_none();
+                    if let Some(deck) = new.as_ref().or(old.as_ref()) {
+                        changes.name = deck.name.clone();
+                    }
+                    let old_cards = cards_by_id(old.as_ref());
+                    let new_cards = cards_by_id(new.as_ref());
+                    let ids: HashSet<&str> = old_cards.keys().chain(new_cards.keys()).copied().collect();
+                    for id in ids {
+                        changes.count_card(old_cards.get(id).copied(), new_cards.get(id).copied());
+                    }
+                }
+                DeckFile::DirectoryMeta { .. } => {
+                    changes.deck_added |= old.is_none() && new.is_some();
+                    changes.deck_removed |= old.is_some() && new.is_none();
+                }
+                DeckFile::DirectoryCard { .. } => {
+                    let old: Option<Card> = old.and_then(|json| serde_json::from_str(&json).ok());
+                    let new: Option<Card> = new.and_then(|json| serde_json::from_str(&json).ok());
+                    changes.count_card(old.as_ref(), new.as_ref());
+                }
+            }
+
+            if changes.name.is_empty() {
+                changes.name = self.directory_deck_name(file.deck_id()).unwrap_or_default();
+            }
+        }
+
+        let total = decks.values().fold(DeckChanges::default(), |mut total, c| {
+            total.reviews += c.reviews;
+            total.added += c.added;
+            total.edited += c.edited;
+            total.removed += c.removed;
+            total
+        });
+        let deck_word = if decks.len() == 1 { "deck" } else { "decks" };
+        let subject = match total.card_summary() {
+            Some(summary) => format!("Sync: {} in {} {}", summary, decks.len(), deck_word),
+            None => format!("Sync: update {} {}", decks.len(), deck_word),
+        };
+
+        let body = decks
+            .iter()
+            .map(|(id, changes)| {
+                let name = if changes.name.is_empty() { id.as_str() } else { changes.name.as_str() };
+                if changes.deck_removed {
+                    format!("- Deleted deck {}", name)
+                } else if changes.deck_added {
+                    format!("- Added deck {} ({} cards)", name, changes.added)
+                } else {
+                    format!("- {}: {}", name, changes.card_summary().unwrap_or_else(|| "details".to_string()))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        (subject, body)
+    }
+
+    fn directory_deck_name(&self, deck_id: &str) -> Option<String> {
+        let meta_path = format!("{}/deck.json", deck_id);
+        let json = fs::read_to_string(self.dir.join(&meta_path))
+            .ok()
+            .or_else(|| self.git(&["show", &format!("HEAD:./{}", meta_path)]).ok())?;
+        let value: serde_json::Value = serde_json::from_str(&json).ok()?;
+        value.get("name")?.as_str().map(str::to_string)
+    }
+
+    /// Merge the upstream branch, resolving deck conflicts card by card.
+    fn merge(&self, remote_ref: &str, report: &mut SyncReport) -> Result<()> {
+        let head = self.git(&["rev-parse", "HEAD"])?;
+        let merge = self.git_committing(&["merge", "--quiet", "--no-edit", "--allow-unrelated-histories", remote_ref]);
+
+        if let Err(merge_error) = merge {
+            let conflicted = self.git(&["diff", "--name-only", "--relative", "--diff-filter=U"])?;
+            if conflicted.is_empty() {
+                return Err(merge_error);
+            }
+
+            for path in conflicted.lines() {
+                let resolved = DeckFile::classify(self.backend, path)
+                    .context("not a deck file")
+                    .and_then(|file| self.resolve(path, &file, &mut report.conflicts))
+                    .with_context(|| format!("Can't merge {} automatically", path));
+                if let Err(e) = resolved {
+                    let _ = self.git(&["merge", "--abort"]);
+                    return Err(e.context("Merge aborted; resolve it in the decks directory with git"));
+                }
+                report.resolved_files += 1;
+            }
+            self.git_committing(&["commit", "--quiet", "--no-edit"])?;
+        }
+
+        report.merged = self.git(&["rev-parse", "HEAD"])? != head;
+        Ok(())
+    }
+
+    /// Resolve one conflicted file and stage the result.
+    fn resolve(&self, path: &str, file: &DeckFile, conflicts: &mut Vec<ContentConflict>) -> Result<()> {
+        let stage = |n: u8| self.git(&["show", &format!(":{}:./{}", n, path)]).ok();
+        let (base, ours, theirs) = (stage(1), stage(2), stage(3));
+
+        let merged: Option<String> = match file {
+            DeckFile::Json { .. } => {
+                let parse = |json: Option<String>| json.map(|j| schema::parse_deck(&j)).transpose();
+                let (base, ours, theirs) = (parse(base)?, parse(ours)?, parse(theirs)?);
+                let merged = match (ours, theirs) {
+                    (Some(ours), Some(theirs)) => Some(merge_decks(base.as_ref(), &ours, &theirs, conflicts)),
+                    (Some(kept), None) | (None, Some(kept)) => (base.as_ref() != Some(&kept)).then_some(kept),
+                    (None, None) => None,
+                };
+                merged
+                    .map(|deck| Ok::<_, anyhow::Error>(serde_json::to_string_pretty(&schema::deck_to_value(&deck)?)?))
+                    .transpose()?
+            }
+            DeckFile::DirectoryCard { deck_id, .. } => {
+                let parse = |json: Option<String>| {
+                    json.map(|j| serde_json::from_str::<Card>(&j)).transpose()
+                };
+                let (base, ours, theirs) = (parse(base)?, parse(ours)?, parse(theirs)?);
+                let merged = match (ours, theirs) {
+                    (Some(ours), Some(theirs)) => {
+                        let deck_name = self.directory_deck_name(deck_id).unwrap_or_else(|| deck_id.clone());
+                        let (card, conflict) = merge_card(base.as_ref(), &ours, &theirs);
+                        conflicts.extend(conflict.map(|(ours, theirs)| ContentConflict {
+                            deck_id: deck_id.clone(),
+                            deck_name,
+                            ours,
+                            theirs,
+                        }));
+                        Some(card)
+                    }
+                    (Some(kept), None) | (None, Some(kept)) => (base.as_ref() != Some(&kept)).then_some(kept),
+                    (None, None) => None,
+                };
+                merged.map(|card| serde_json::to_string_pretty(&card)).transpose()?
+            }
+            DeckFile::DirectoryMeta { .. } => {
+                let parse = |json: Option<String>| {
+                    json.map(|j| serde_json::from_str::<serde_json::Value>(&j)).transpose()
+                };
+                let (ours, theirs) = (parse(ours)?, parse(theirs)?);
+                match (ours, theirs) {
+                    (Some(ours), Some(theirs)) => Some(serde_json::to_string_pretty(&merge_card_order(ours, &theirs))?),
+                    (Some(kept), None) | (None, Some(kept)) => Some(serde_json::to_string_pretty(&kept)?),
+                    (None, None) => None,
+                }
+            }
+        };
+
+        let full_path = self.dir.join(path);
+        match merged {
+            Some(contents) => {
+                fs::write(&full_path, contents)?;
+                self.git(&["add", "--", path])?;
+            }
+            None => {
+                let _ = fs::remove_file(&full_path);
+                self.git(&["rm", "--quiet", "--cached", "--ignore-unmatch", "--", path])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A deck file path within the decks directory.
+enum DeckFile {
+    /// `<id>.json` (json backend)
+    Json { deck_id: String },
+    /// `<id>/deck.json` (directory backend)
+    DirectoryMeta { deck_id: String },
+    /// `<id>/cards/<card>.json` (directory backend)
+    DirectoryCard { deck_id: String },
+}
+
+impl DeckFile {
+    fn classify(backend: StorageBackend, path: &str) -> Option<Self> {
+        let parts: Vec<&str> = Path::new(path).iter().filter_map(|p| p.to_str()).collect();
+        match (backend, parts.as_slice()) {
+            (StorageBackend::Json, [file]) => file
+                .strip_suffix(".json")
+                .map(|id| DeckFile::Json { deck_id: id.to_string() }),
+            (StorageBackend::Directory, [id, "deck.json"]) => {
+                Some(DeckFile::DirectoryMeta { deck_id: id.to_string() })
+            }
+            (StorageBackend::Directory, [id, "cards", card]) if card.ends_with(".json") => {
+                Some(DeckFile::DirectoryCard { deck_id: id.to_string() })
+            }
+            _ => None,
+        }
+    }
+
+    fn deck_id(&self) -> &str {
+        match self {
+            DeckFile::Json { deck_id } | DeckFile::DirectoryMeta { deck_id } | DeckFile::DirectoryCard { deck_id } => {
+                deck_id
+            }
+        }
+    }
+}
+
+/// Per-deck tally for commit messages.
+#[derive(Default)]
+struct DeckChanges {
+    name: String,
+    reviews: u32,
+    added: usize,
+    edited: usize,
+    removed: usize,
+    deck_added: bool,
+    deck_removed: bool,
+}
+
+impl DeckChanges {
+    fn count_card(&mut self, old: Option<&Card>, new: Option<&Card>) {
+        match (old, new) {
+            (None, Some(_)) => self.added += 1,
+            (Some(_), None) => self.removed += 1,
+            (Some(old), Some(new)) => {
+                self.reviews += new.total_reviews.saturating_sub(old.total_reviews);
+                if old.front != new.front || old.back != new.back {
+                    self.edited += 1;
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// e.g. "12 reviews, 2 cards added", or `None` if no card changed.
+    fn card_summary(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            (self.reviews as usize, "reviews"),
+            (self.added, "cards added"),
+            (self.edited, "cards edited"),
+            (self.removed, "cards removed"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, what)| format!("{} {}", count, what))
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+/// Merge a directory deck's `deck.json`: keep our metadata and card order,
+/// appending cards only the other side has.
+fn merge_card_order(mut ours: serde_json::Value, theirs: &serde_json::Value) -> serde_json::Value {
+    let order = |v: &serde_json::Value| -> Vec<String> {
+        v.get("card_order")
+            .and_then(|o| serde_json::from_value(o.clone()).ok())
+            .unwrap_or_default()
+    };
+    let mut merged = order(&ours);
+    for id in order(theirs) {
+        if !merged.contains(&id) {
+            merged.push(id);
+        }
+    }
+    if let Some(obj) = ours.as_object_mut() {
+        obj.insert("card_order".to_string(), serde_json::Value::from(merged));
+    }
+    ours
+}
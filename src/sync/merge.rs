@@ -0,0 +1,127 @@
+//! Card-level merging of decks changed in two places, shared by the sync
+//! methods and by saves that find the deck changed by another process.
+//!
+//! Scheduling is driven by the review log: a card takes its scheduling from
+//! the side with the latest review, and reviews made only on the other side
+//! are added to its log and counts. Text changes are merged against the
+//! common base; cards edited differently on both sides keep the local text
+//! and are reported as `ContentConflict`s.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{Card, Deck, ReviewRating};
+
+/// A card whose text was changed differently on both sides.
+/// The local text was kept.
+#[derive(Debug, Clone)]
+pub struct ContentConflict {
+    pub deck_id: String,
+    pub deck_name: String,
+    pub ours: Card,
+    pub theirs: Card,
+}
+
+pub(super) fn cards_by_id(deck: Option<&Deck>) -> HashMap<&str, &Card> {
+    deck.map(|d| d.cards.iter().map(|c| (c.id.as_str(), c)).collect())
+        .unwrap_or_default()
+}
+
+/// Three-way merge of a deck changed on both sides. Cards deleted on one
+/// side stay deleted unless the other side changed them.
+pub fn merge_decks(
+    base: Option<&Deck>,
+    ours: &Deck,
+    theirs: &Deck,
+    conflicts: &mut Vec<ContentConflict>,
+) -> Deck {
+    let base_cards = cards_by_id(base);
+    let their_cards = cards_by_id(Some(theirs));
+    let our_ids: HashSet<&str> = ours.cards.iter().map(|c| c.id.as_str()).collect();
+
+    let mut merged = ours.clone();
+    if let Some(base) = base {
+        if ours.name == base.name {
+            merged.name = theirs.name.clone();
+        }
+        if ours.description == base.description {
+            merged.description = theirs.description.clone();
+        }
+    }
+    merged.last_studied = ours.last_studied.max(theirs.last_studied);
+
+    merged.cards = Vec::new();
+    for card in &ours.cards {
+        let base_card = base_cards.get(card.id.as_str()).copied();
+        match their_cards.get(card.id.as_str()) {
+            Some(their_card) => {
+                let (card, conflict) = merge_card(base_card, card, their_card);
+                conflicts.extend(conflict.map(|(ours, theirs)| ContentConflict {
+                    deck_id: merged.id.clone(),
+                    deck_name: merged.name.clone(),
+                    ours,
+                    theirs,
+                }));
+                merged.cards.push(card);
+            }
+            // Deleted there; keep only if changed here
+            None if base_card == Some(card) => {}
+            None => merged.cards.push(card.clone()),
+        }
+    }
+    for card in &theirs.cards {
+        let deleted_here_unchanged = base_cards.get(card.id.as_str()) == Some(&card);
+        if !our_ids.contains(card.id.as_str()) && !deleted_here_unchanged {
+            merged.cards.push(card.clone());
+        }
+    }
+
+    merged
+}
+
+/// When a card was last reviewed according to its review log, falling back
+/// to `last_reviewed` for cards reviewed before the log existed.
+fn latest_review(card: &Card) -> Option<chrono::DateTime<chrono::Local>> {
+    card.reviews.iter().map(|r| r.reviewed_at).max().max(card.last_reviewed)
+}
+
+/// Merge two versions of a card. Scheduling comes from the side reviewed
+/// last, with the other side's reviews added to the log; text from the side
+/// that changed it. If both changed the text differently, ours is kept and
+/// both versions are returned as a conflict.
+pub(super) fn merge_card(base: Option<&Card>, ours: &Card, theirs: &Card) -> (Card, Option<(Card, Card)>) {
+    let (mut merged, other) = if latest_review(theirs) > latest_review(ours) {
+        (theirs.clone(), ours)
+    } else {
+        (ours.clone(), theirs)
+    };
+
+    // Logs only grow, so reviews missing here were made on the other side only
+    for review in &other.reviews {
+        if !merged.reviews.iter().any(|r| r.reviewed_at == review.reviewed_at) {
+            merged.total_reviews += 1;
+            if review.rating == ReviewRating::Again {
+                merged.lapses += 1;
+            }
+            merged.reviews.push(review.clone());
+        }
+    }
+    merged.reviews.sort_by_key(|r| r.reviewed_at);
+
+    let text = |c: &Card| (c.front.clone(), c.back.clone(), c.notes.clone(), c.tags.clone());
+    let ours_changed = base.is_none_or(|b| text(b) != text(ours));
+    let theirs_changed = base.is_none_or(|b| text(b) != text(theirs));
+
+    let (text_from, conflict) = if text(ours) == text(theirs) || !theirs_changed {
+        (ours, None)
+    } else if !ours_changed {
+        (theirs, None)
+    } else {
+        (ours, Some((ours.clone(), theirs.clone())))
+    };
+    merged.front = text_from.front.clone();
+    merged.back = text_from.back.clone();
+    merged.notes = text_from.notes.clone();
+    merged.tags = text_from.tags.clone();
+
+    (merged, conflict)
+}
@@ -0,0 +1,30 @@
+//! Syncing decks between machines, either through an srl sync server or
+//! through git.
+
+mod client;
+mod git;
+mod merge;
+pub mod protocol;
+
+pub use client::{PushRejected, SyncClient};
+pub use git::GitSync;
+pub use merge::{merge_decks, ContentConflict};
+
+use anyhow::Result;
+
+use crate::config::SyncConfig;
+use crate::storage::DeckStorage;
+
+/// Sync with the configured sync server, or through git if there is none.
+/// Returns a summary and the cards edited differently on both sides.
+pub fn run(storage: &DeckStorage, config: &SyncConfig) -> Result<(String, Vec<ContentConflict>)> {
+    if config.server.is_some() {
+        let report = storage.sync_with_server(&SyncClient::new(config)?)?;
+        let summary = report.to_string();
+        Ok((summary, report.conflicts))
+    } else {
+        let report = GitSync::for_storage(storage)?.sync(storage)?;
+        let summary = report.to_string();
+        Ok((summary, report.conflicts))
+    }
+}
@@ -0,0 +1,112 @@
+//! Wire format of the sync server protocol.
+//!
+//! A collection on the server is a set of entities (deck metadata and cards),
+//! each stamped with the update sequence number (USN) of the push that last
+//! changed it. The server's USN goes up by one per accepted push, so a client
+//! that remembers the USN it last synced at can ask for everything changed
+//! since. Deleted entities are kept as tombstones with `data: None`.
+//!
+//! ```text
+//! GET  /v1/collections/<name>/changes?since=<usn>   -> ChangesResponse
+//! POST /v1/collections/<name>/changes  PushRequest  -> PushResponse
+//! ```
+//!
+//! A push is rejected with `409 Conflict` unless its `base_usn` is the
+//! collection's current USN, i.e. the client has merged every change pushed
+//! by other clients first. Requests carry `Authorization: Bearer <token>`
+//! when the server was started with a token.
+//!
+//! This file is also compiled into `srl-sync-server`, so it only depends on
+//! serde.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Bumped when the wire format changes incompatibly.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Header carrying `PROTOCOL_VERSION` on every request and response.
+pub const VERSION_HEADER: &str = "X-Srl-Sync-Version";
+
+/// What an entity holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    /// Deck metadata and card order; `id` is the deck ID.
+    Deck,
+    /// A single card, including its review log.
+    Card,
+}
+
+/// A changed deck or card.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub kind: EntityKind,
+    pub deck_id: String,
+    pub id: String,
+    /// Assigned by the server; ignored in pushes.
+    #[serde(default)]
+    pub usn: u64,
+    /// The entity's JSON, or `None` if it was deleted.
+    pub data: Option<Value>,
+}
+
+impl Change {
+    /// Key identifying the entity within a collection.
+    pub fn key(&self) -> String {
+        match self.kind {
+            EntityKind::Deck => format!("deck/{}", self.deck_id),
+            EntityKind::Card => format!("card/{}/{}", self.deck_id, self.id),
+        }
+    }
+
+    /// Whether the change's IDs are valid and agree with each other and the
+    /// entity's data.
+    pub fn is_well_formed(&self) -> bool {
+        valid_entity_id(&self.deck_id)
+            && valid_entity_id(&self.id)
+            && (self.kind == EntityKind::Card || self.id == self.deck_id)
+            && self
+                .data
+                .as_ref()
+                .is_none_or(|data| data.get("id").and_then(Value::as_str) == Some(self.id.as_str()))
+    }
+}
+
+/// Everything changed since the requested USN, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangesResponse {
+    /// The collection's current USN.
+    pub usn: u64,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushRequest {
+    /// USN the client's changes were merged against.
+    pub base_usn: u64,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushResponse {
+    /// The collection's USN after applying the push.
+    pub usn: u64,
+}
+
+/// Whether `id` is usable as a deck or card ID. Clients name files after
+/// them, so they can't be empty, hidden, or hold path separators or `..`.
+pub fn valid_entity_id(id: &str) -> bool {
+    !id.is_empty()
+        && !id.starts_with('.')
+        && !id.contains("..")
+        && !id.contains(['/', '\\', '\0'])
+}
+
+/// Whether `name` is usable as a collection name (it becomes a file name on
+/// the server).
+pub fn valid_collection_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
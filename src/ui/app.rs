@@ -1,7 +1,11 @@
 //! Main application state and logic.
 
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::time::Instant;
 
+use anyhow::Context;
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
 use ratatui::{
     layout::{Alignment, Constraint, Layout, Rect},
@@ -11,12 +15,19 @@ use ratatui::{
     Frame,
 };
 
+use super::import::{ImportWizard, WizardAction, IMPORT_EXTENSIONS};
+use super::picker::{FilePicker, PickerAction};
 use super::theme::Theme;
 use super::widgets::{CompletionScreen, FlashcardWidget, KeyHints, Logo, RatingButtons, StatsBar};
 use crate::config::Config;
-use crate::models::{Deck, ReviewRating};
+use crate::formats::{AnkiPackageExporter, CsvExporter, DeckExporter, MarkdownExporter};
+use crate::models::{media_refs, Deck, ReviewRating};
 use crate::sm2::Scheduler;
-use crate::storage::{DeckInfo, DeckStorage};
+use crate::sync::{self, ContentConflict};
+use crate::storage::{
+    BackupEntry, BackupRotation, BrokenDeck, DeckConflict, DeckInfo, DeckStorage, DeckWatcher,
+    Locked, RestoreMode,
+};
 
 // ══════════════════════════════════════════════════════════════════════════
 // Application State
@@ -30,6 +41,18 @@ pub enum Screen {
     CardBrowser,
     Stats,
     Complete,
+    Backups,
+    SyncConflicts,
+    Import,
+}
+
+/// File formats the deck list exports to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    AnkiPackage,
+    Backup,
+    Csv,
+    Markdown,
 }
 
 pub struct App {
@@ -43,14 +66,30 @@ pub struct App {
     // Storage
     pub storage: DeckStorage,
     pub scheduler: Scheduler,
+    watcher: Option<DeckWatcher>,  // Reports decks changed by other programs
 
     // Deck selection
     pub deck_list: Vec<DeckInfo>,
     pub deck_list_state: ListState,
+    pub marked_decks: HashSet<String>,  // IDs of decks picked for export
+    pub export_menu: bool,  // Export format popup is open
+    pub import_picker: Option<FilePicker>,  // Set while on the import screen
+    pub import_wizard: Option<ImportWizard>,  // Set once a file is picked
+
+    // Deck files that failed to load
+    pub broken_decks: Vec<BrokenDeck>,
+    pub broken_list_state: ListState,
+    pub broken_focus: bool,  // True while the warning panel has keyboard focus
+
+    // File to open in an external editor (handled by the main loop)
+    pub pending_edit: Option<PathBuf>,
 
     // Current deck
     pub current_deck: Option<Deck>,
 
+    // Save refused because another process changed the deck; awaiting a choice
+    pub save_conflict: Option<DeckConflict>,
+
     // Study state
     pub study_queue: Vec<usize>,  // Indices into deck.cards
     pub current_card_idx: Option<usize>,
@@ -74,14 +113,30 @@ pub struct App {
     pub card_edit_cursor: usize, // cursor position in current field
     pub card_delete_pending: bool,
 
+    // Stats screen, computed once when the screen is opened
+    pub collection_stats: Option<CollectionStats>,
+
+    // Automatic backups screen
+    pub backup_rotation: BackupRotation,
+    pub backup_list: Vec<BackupEntry>,
+    pub backup_list_state: ListState,
+    pub backup_overwrite_pending: bool,  // Overwrite needs a second keypress
+
+    // Cards edited differently on both sides of the last sync
+    pub sync_conflicts: Vec<ContentConflict>,
+    pub sync_conflict_state: ListState,
+
     // Status message (shown temporarily)
     pub status_message: Option<(String, Instant)>,
 }
 
 impl App {
     pub fn new(storage: DeckStorage, config: Config) -> Self {
-        let deck_list = storage.list_decks().unwrap_or_default();
+        let (deck_list, broken_decks) = storage.scan_decks().unwrap_or_default();
         let theme = Theme::from_name(&config.theme);
+        // Without a watcher the app still works, it just won't notice outside edits
+        let watcher = storage.watch().ok().flatten();
+        let backup_rotation = BackupRotation::new(&config.backup);
 
         Self {
             screen: Screen::DeckSelect,
@@ -90,9 +145,19 @@ impl App {
             theme,
             storage,
             scheduler: Scheduler::new(),
+            watcher,
             deck_list,
             deck_list_state: ListState::default().with_selected(Some(0)),
+            marked_decks: HashSet::new(),
+            export_menu: false,
+            import_picker: None,
+            import_wizard: None,
+            broken_list_state: ListState::default().with_selected(Some(0)),
+            broken_decks,
+            broken_focus: false,
+            pending_edit: None,
             current_deck: None,
+            save_conflict: None,
             study_queue: Vec::new(),
             current_card_idx: None,
             showing_answer: false,
@@ -116,6 +181,13 @@ impl App {
             card_edit_focus: 0,
             card_edit_cursor: 0,
             card_delete_pending: false,
+            collection_stats: None,
+            backup_rotation,
+            backup_list: Vec::new(),
+            backup_list_state: ListState::default(),
+            backup_overwrite_pending: false,
+            sync_conflicts: Vec::new(),
+            sync_conflict_state: ListState::default(),
             // Status
             status_message: None,
         }
@@ -145,7 +217,56 @@ impl App {
     }
 
     pub fn refresh_deck_list(&mut self) {
-        self.deck_list = self.storage.list_decks().unwrap_or_default();
+        let selected_id = self
+            .deck_list_state
+            .selected()
+            .and_then(|i| self.deck_list.get(i))
+            .map(|d| d.id.clone());
+
+        let (deck_list, broken_decks) = self.storage.scan_decks().unwrap_or_default();
+        self.deck_list = deck_list;
+        self.broken_decks = broken_decks;
+
+        // Keep the same deck selected when others are added or removed
+        let selected = selected_id
+            .and_then(|id| self.deck_list.iter().position(|d| d.id == id))
+            .or(self.deck_list_state.selected())
+            .map(|i| i.min(self.deck_list.len().saturating_sub(1)));
+        self.deck_list_state.select(selected);
+
+        // Keep the warning panel selection in range
+        if self.broken_decks.is_empty() {
+            self.broken_focus = false;
+            self.broken_list_state.select(Some(0));
+        } else if self.broken_list_state.selected().unwrap_or(0) >= self.broken_decks.len() {
+            self.broken_list_state.select(Some(self.broken_decks.len() - 1));
+        }
+    }
+
+    pub fn selected_broken_deck(&self) -> Option<&BrokenDeck> {
+        self.broken_list_state
+            .selected()
+            .and_then(|i| self.broken_decks.get(i))
+    }
+
+    pub fn quarantine_selected_broken_deck(&mut self) {
+        if let Some(broken) = self.selected_broken_deck().cloned() {
+            match self.storage.quarantine_deck(&broken) {
+                Ok(target) => {
+                    self.set_status(format!("Moved {} to {}", file_label(&broken.path), target.display()));
+                }
+                Err(e) => {
+                    self.set_status(format!("Quarantine failed: {}", e));
+                }
+            }
+            self.refresh_deck_list();
+        }
+    }
+
+    pub fn repair_selected_broken_deck(&mut self) {
+        if let Some(broken) = self.selected_broken_deck() {
+            self.pending_edit = Some(broken.path.clone());
+        }
     }
 
     pub fn select_deck(&mut self, deck_id: &str) {
@@ -168,7 +289,7 @@ impl App {
 
             // Add due cards first
             for (i, card) in deck.cards.iter().enumerate() {
-                if card.is_due() && !card.is_new() {
+                if card.is_due() && !card.is_new() && !card.is_held() {
                     self.study_queue.push(i);
                 }
             }
@@ -176,7 +297,7 @@ impl App {
             // Add new cards (limit to 20)
             let mut new_count = 0;
             for (i, card) in deck.cards.iter().enumerate() {
-                if card.is_new() && new_count < 20 {
+                if card.is_new() && !card.is_held() && new_count < 20 {
                     self.study_queue.push(i);
                     new_count += 1;
                 }
@@ -211,6 +332,38 @@ impl App {
         self.answer_revealed = true;
     }
 
+    /// Media references on the side of the current card being shown.
+    fn shown_media(&self) -> Vec<String> {
+        let (Some(deck), Some(idx)) = (&self.current_deck, self.current_card_idx) else {
+            return Vec::new();
+        };
+        let card = &deck.cards[idx];
+        let side = if self.showing_answer { &card.back } else { &card.front };
+        media_refs(side).iter().map(|m| m.name.to_string()).collect()
+    }
+
+    /// Open the media on the shown side of the current card in the system's
+    /// default viewer.
+    pub fn open_card_media(&mut self) {
+        let names = self.shown_media();
+        let Some(deck_id) = self.current_deck.as_ref().map(|d| d.id.clone()) else {
+            return;
+        };
+        if names.is_empty() {
+            self.set_status("No media on this side of the card".to_string());
+            return;
+        }
+
+        let opened = names.iter().try_for_each(|name| {
+            let path = self.storage.media_for_viewing(&deck_id, name)?;
+            open_externally(&path)
+        });
+        match opened {
+            Ok(()) => self.set_status(format!("Opened {}", names.join(", "))),
+            Err(e) => self.set_status(format!("Can't open media: {:#}", e)),
+        }
+    }
+
     pub fn rate_card(&mut self, rating: ReviewRating) {
         if !self.answer_revealed {
             return;
@@ -225,8 +378,8 @@ impl App {
                 self.study_queue.push(idx);
             }
 
-            // Save deck
-            let _ = self.storage.save_deck(deck);
+            // Save the reviewed card
+            self.save_current_deck(Some(idx));
 
             self.next_card();
         }
@@ -239,7 +392,7 @@ impl App {
 
         if let Some(ref mut deck) = self.current_deck {
             deck.add_card(self.add_card_front.clone(), self.add_card_back.clone());
-            let _ = self.storage.save_deck(deck);
+            self.save_current_deck(None);
 
             self.add_card_front.clear();
             self.add_card_back.clear();
@@ -257,27 +410,81 @@ impl App {
         self.status_message = Some((message, Instant::now()));
     }
 
-    pub fn export_backup(&mut self) {
-        let path = DeckStorage::default_backup_path();
-        match self.storage.export_backup(&path) {
-            Ok(count) => {
-                self.set_status(format!("Exported {} decks to {}", count, path.display()));
+    pub fn toggle_deck_mark(&mut self) {
+        if let Some(deck_info) = self.deck_list_state.selected().and_then(|i| self.deck_list.get(i)) {
+            if !self.marked_decks.remove(&deck_info.id) {
+                self.marked_decks.insert(deck_info.id.clone());
+            }
+        }
+    }
+
+    /// IDs of the marked decks still in the deck list, in list order.
+    fn marked_deck_ids(&self) -> Vec<String> {
+        self.deck_list
+            .iter()
+            .filter(|d| self.marked_decks.contains(&d.id))
+            .map(|d| d.id.clone())
+            .collect()
+    }
+
+    /// Export the marked decks, or every deck if none are marked, to a
+    /// timestamped file in the documents directory.
+    pub fn export_decks(&mut self, format: ExportFormat) {
+        self.export_menu = false;
+        let ids = self.marked_deck_ids();
+        let selection = if ids.is_empty() { None } else { Some(ids.as_slice()) };
+
+        let result = self.storage.load_decks(selection).and_then(|decks| {
+            let (path, cards) = match format {
+                ExportFormat::AnkiPackage => {
+                    let path = DeckStorage::default_export_path("srl_export", "apkg");
+                    let cards = AnkiPackageExporter::with_media(&self.storage).export(&decks, &path)?;
+                    (path, cards)
+                }
+                ExportFormat::Backup => {
+                    let path = DeckStorage::default_backup_path();
+                    self.storage.export_decks_backup(&decks, &path)?;
+                    (path, decks.iter().map(|d| d.cards.len()).sum())
+                }
+                ExportFormat::Csv => {
+                    let path = DeckStorage::default_export_path("srl_export", "csv");
+                    let cards = CsvExporter.export(&decks, &path)?;
+                    (path, cards)
+                }
+                ExportFormat::Markdown => {
+                    // Several decks go in a folder, a file each
+                    let path = DeckStorage::default_export_path("srl_export", "md");
+                    let path = if decks.len() == 1 { path } else { path.with_extension("") };
+                    let cards = MarkdownExporter.export(&decks, &path)?;
+                    (path, cards)
+                }
+            };
+            Ok((decks.len(), cards, path))
+        });
+
+        match result {
+            Ok((decks, cards, path)) => {
+                self.marked_decks.clear();
+                self.set_status(format!("Exported {} cards from {} decks to {}", cards, decks, path.display()));
             }
             Err(e) => {
-                self.set_status(format!("Export failed: {}", e));
+                self.set_status(format!("Export failed: {:#}", e));
             }
         }
     }
 
-    pub fn import_backup(&mut self, path: &std::path::Path) {
-        match self.storage.import_backup(path) {
-            Ok((imported, skipped)) => {
+    pub fn import_backup(&mut self, path: &std::path::Path, mode: RestoreMode) {
+        match self.storage.import_backup(path, mode, None, false) {
+            Ok(report) => {
                 self.refresh_deck_list();
-                if skipped > 0 {
-                    self.set_status(format!("Imported {} decks ({} skipped - already exist)", imported, skipped));
-                } else {
-                    self.set_status(format!("Imported {} decks", imported));
-                }
+                self.set_status(report.to_string());
+            }
+            // There's no prompt for another passphrase here
+            Err(e) if e.is::<Locked>() => {
+                self.set_status(format!(
+                    "{} is encrypted with another passphrase; restore it with srl --import-backup",
+                    path.display()
+                ));
             }
             Err(e) => {
                 self.set_status(format!("Import failed: {}", e));
@@ -285,6 +492,102 @@ impl App {
         }
     }
 
+    pub fn open_backups(&mut self) {
+        self.backup_overwrite_pending = false;
+        self.refresh_backup_list();
+        self.backup_list_state = ListState::default().with_selected(Some(0));
+        self.screen = Screen::Backups;
+    }
+
+    fn refresh_backup_list(&mut self) {
+        match self.backup_rotation.list() {
+            Ok(backups) => self.backup_list = backups,
+            Err(e) => {
+                self.backup_list.clear();
+                self.set_status(format!("Could not list backups: {}", e));
+            }
+        }
+        if self.backup_list_state.selected().unwrap_or(0) >= self.backup_list.len() {
+            self.backup_list_state.select(Some(self.backup_list.len().saturating_sub(1)));
+        }
+    }
+
+    pub fn create_backup_now(&mut self) {
+        match self.backup_rotation.create(&self.storage) {
+            Ok(path) => self.set_status(format!("Backed up to {}", path.display())),
+            Err(e) => self.set_status(format!("Backup failed: {:#}", e)),
+        }
+        self.refresh_backup_list();
+        self.backup_list_state.select(Some(0));
+    }
+
+    pub fn restore_selected_backup(&mut self, mode: RestoreMode) {
+        let Some(backup) = self.backup_list_state.selected().and_then(|i| self.backup_list.get(i)) else {
+            return;
+        };
+        let path = backup.path.clone();
+        self.import_backup(&path, mode);
+    }
+
+    /// Sync with the configured server, or commit, pull, and push the decks
+    /// directory with git.
+    pub fn sync_now(&mut self) {
+        match sync::run(&self.storage, &self.config.sync) {
+            Ok((summary, conflicts)) => {
+                self.refresh_deck_list();
+                // A deck removed by the sync replaces the summary
+                self.set_status(summary);
+                self.reload_current_deck();
+                if !conflicts.is_empty() {
+                    self.sync_conflicts = conflicts;
+                    self.sync_conflict_state = ListState::default().with_selected(Some(0));
+                    self.screen = Screen::SyncConflicts;
+                }
+            }
+            Err(e) => self.set_status(format!("Sync failed: {:#}", e)),
+        }
+    }
+
+    /// Settle the selected sync conflict, optionally replacing the local
+    /// card text with the remote one.
+    fn resolve_sync_conflict(&mut self, take_theirs: bool) {
+        let Some(i) = self.sync_conflict_state.selected().filter(|&i| i < self.sync_conflicts.len()) else {
+            return;
+        };
+        let conflict = self.sync_conflicts.remove(i);
+
+        if take_theirs {
+            let result = self.storage.load_deck(&conflict.deck_id).and_then(|deck| {
+                let mut deck = deck.context("deck no longer exists")?;
+                let card = deck
+                    .cards
+                    .iter_mut()
+                    .find(|c| c.id == conflict.theirs.id)
+                    .context("card no longer exists")?;
+                card.front = conflict.theirs.front.clone();
+                card.back = conflict.theirs.back.clone();
+                card.notes = conflict.theirs.notes.clone();
+                card.tags = conflict.theirs.tags.clone();
+                self.storage.save_deck(&deck)
+            });
+            match result {
+                Ok(()) => self.set_status("Took the remote text; it is sent on the next sync".to_string()),
+                Err(e) => self.set_status(format!("Could not update card: {:#}", e)),
+            }
+        }
+
+        if self.sync_conflicts.is_empty() {
+            self.screen = Screen::DeckSelect;
+        } else if i >= self.sync_conflicts.len() {
+            self.sync_conflict_state.select(Some(self.sync_conflicts.len() - 1));
+        }
+    }
+
+    pub fn open_stats(&mut self) {
+        self.collection_stats = Some(CollectionStats::compute(&self.storage, &self.deck_list));
+        self.screen = Screen::Stats;
+    }
+
     pub fn enter_card_browser(&mut self) {
         if let Some(ref deck) = self.current_deck {
             if !deck.cards.is_empty() {
@@ -331,7 +634,7 @@ impl App {
                 if let Some(card) = deck.cards.get(i) {
                     let card_id = card.id.clone();
                     deck.update_card(&card_id, self.card_edit_front.clone(), self.card_edit_back.clone());
-                    let _ = self.storage.save_deck(deck);
+                    self.save_current_deck(Some(i));
                 }
             }
         }
@@ -352,20 +655,200 @@ impl App {
                 if let Some(card) = deck.cards.get(i) {
                     let card_id = card.id.clone();
                     deck.delete_card(&card_id);
-                    let _ = self.storage.save_deck(deck);
-
-                    // Adjust selection
-                    if deck.cards.is_empty() {
-                        self.card_list_state.select(None);
-                    } else if i >= deck.cards.len() {
-                        self.card_list_state.select(Some(deck.cards.len() - 1));
-                    }
+                    self.save_current_deck(None);
+                    self.clamp_card_selection();
                 }
             }
         }
         self.card_delete_pending = false;
     }
 
+    /// Suspend the selected card, or bring back a suspended or buried one.
+    pub fn toggle_suspend_selected_card(&mut self) {
+        let Some(i) = self.card_list_state.selected() else {
+            return;
+        };
+        let Some(card) = self.current_deck.as_mut().and_then(|d| d.cards.get_mut(i)) else {
+            return;
+        };
+        let held = card.is_held();
+        card.suspended = !held;
+        card.buried_until = None;
+        self.save_current_deck(Some(i));
+        self.set_status(if held { "Card unsuspended" } else { "Card suspended" }.to_string());
+    }
+
+    fn clamp_card_selection(&mut self) {
+        let len = self.current_deck.as_ref().map_or(0, |d| d.cards.len());
+        match self.card_list_state.selected() {
+            _ if len == 0 => self.card_list_state.select(None),
+            Some(i) if i >= len => self.card_list_state.select(Some(len - 1)),
+            _ => {}
+        }
+    }
+
+    /// Save the current deck, or just one of its cards.
+    /// If another process changed the deck meanwhile, nothing is written and
+    /// the user is asked how to resolve it.
+    fn save_current_deck(&mut self, card_idx: Option<usize>) {
+        let Some(ref deck) = self.current_deck else {
+            return;
+        };
+        let result = match card_idx {
+            Some(idx) => self.storage.save_card(deck, idx),
+            None => self.storage.save_deck(deck),
+        };
+
+        if let Err(e) = result {
+            match e.downcast::<DeckConflict>() {
+                Ok(conflict) => self.save_conflict = Some(conflict),
+                Err(e) => self.set_status(format!("Save failed: {}", e)),
+            }
+        }
+    }
+
+    fn handle_conflict_keys(&mut self, key: KeyCode) {
+        let (Some(conflict), Some(deck)) = (self.save_conflict.as_ref(), self.current_deck.as_ref()) else {
+            self.save_conflict = None;
+            return;
+        };
+
+        let result = match key {
+            // Reload: drop our unsaved changes
+            KeyCode::Char('r') => self.storage.load_deck(&conflict.deck_id).map(|stored| {
+                let message = format!("Reloaded '{}' from disk", conflict.deck_name);
+                (stored, message)
+            }),
+            KeyCode::Char('m') => self.storage.merge_deck(deck).map(|(merged, conflicts)| {
+                let mut message = format!("Merged changes to '{}'", conflict.deck_name);
+                if !conflicts.is_empty() {
+                    message.push_str(&format!("; kept this copy's text of {} cards edited in both", conflicts.len()));
+                }
+                (Some(merged), message)
+            }),
+            KeyCode::Char('o') => self.storage.overwrite_deck(deck).map(|()| {
+                let message = format!("Overwrote '{}' with this copy", conflict.deck_name);
+                (Some(deck.clone()), message)
+            }),
+            KeyCode::Esc => {
+                let message = format!("'{}' not saved; the next change will ask again", conflict.deck_name);
+                self.save_conflict = None;
+                self.set_status(message);
+                return;
+            }
+            _ => return,
+        };
+        self.save_conflict = None;
+
+        match result {
+            Ok((Some(deck), message)) => {
+                self.replace_current_deck(deck);
+                self.set_status(message);
+            }
+            Ok((None, _)) => {
+                self.current_deck = None;
+                self.screen = Screen::DeckSelect;
+                self.refresh_deck_list();
+                self.set_status("Deck was deleted by another process".to_string());
+            }
+            Err(e) => self.set_status(format!("Save failed: {}", e)),
+        }
+    }
+
+    /// Pick up decks added, removed, or edited by other programs.
+    /// Called from the main loop; cheap when nothing changed.
+    pub fn poll_external_changes(&mut self) {
+        if !self.watcher.as_mut().is_some_and(|w| w.poll()) {
+            return;
+        }
+        self.refresh_deck_list();
+
+        // Unsaved changes here are settled through the conflict prompt instead
+        let Some(deck) = self.current_deck.as_ref() else {
+            return;
+        };
+        if self.save_conflict.is_some() || self.storage.has_unsaved_changes(deck) {
+            return;
+        }
+
+        let deck_name = deck.name.clone();
+        match self.storage.load_deck(&deck.id) {
+            Ok(Some(stored)) if self.current_deck.as_ref() != Some(&stored) => {
+                self.replace_current_deck(stored);
+            }
+            Ok(None) => self.close_deleted_deck(format!("'{}' was deleted outside srl", deck_name)),
+            // Unchanged, or mid-write by another program; the next change retries
+            _ => {}
+        }
+    }
+
+    /// Pick up changes a sync in this process saved to the current deck.
+    /// The watcher can't tell those from unsaved edits made here.
+    fn reload_current_deck(&mut self) {
+        let Some((deck_id, deck_name)) = self.current_deck.as_ref().map(|d| (d.id.clone(), d.name.clone())) else {
+            return;
+        };
+        match self.storage.load_deck(&deck_id) {
+            Ok(Some(stored)) if self.current_deck.as_ref() != Some(&stored) => {
+                self.replace_current_deck(stored);
+            }
+            Ok(None) => self.close_deleted_deck(format!("'{}' was deleted by the sync", deck_name)),
+            _ => {}
+        }
+    }
+
+    /// Leave a deck that no longer exists for the deck list.
+    fn close_deleted_deck(&mut self, status: String) {
+        self.current_deck = None;
+        self.card_edit_mode = false;
+        self.screen = Screen::DeckSelect;
+        self.set_status(status);
+    }
+
+    /// Swap in a newer copy of the current deck without disturbing the
+    /// study session: queued cards and selections follow their card IDs, and
+    /// cards added elsewhere aren't queued until the deck is reopened.
+    fn replace_current_deck(&mut self, deck: Deck) {
+        let old_ids: Vec<String> = self
+            .current_deck
+            .as_ref()
+            .map(|d| d.cards.iter().map(|c| c.id.clone()).collect())
+            .unwrap_or_default();
+        let new_index = |i: usize| {
+            old_ids
+                .get(i)
+                .and_then(|id| deck.cards.iter().position(|c| c.id == *id))
+        };
+
+        self.study_queue = self.study_queue.iter().filter_map(|&i| new_index(i)).collect();
+        let current = self.current_card_idx.map(new_index);
+        let selected = self.card_list_state.selected().map(new_index);
+        self.current_deck = Some(deck);
+
+        match selected {
+            Some(Some(i)) => self.card_list_state.select(Some(i)),
+            Some(None) => {
+                if self.card_edit_mode {
+                    self.cancel_card_edit();
+                    self.set_status("The card being edited was deleted outside srl".to_string());
+                }
+                self.clamp_card_selection();
+            }
+            None => {}
+        }
+
+        match current {
+            Some(Some(i)) => {
+                self.current_card_idx = Some(i);
+                if let Some(ref deck) = self.current_deck {
+                    self.interval_preview = self.scheduler.preview_intervals(&deck.cards[i]);
+                }
+            }
+            Some(None) if self.screen == Screen::Study => self.next_card(),
+            _ => {}
+        }
+    }
+
     // ══════════════════════════════════════════════════════════════════════
     // Event Handling
     // ══════════════════════════════════════════════════════════════════════
@@ -377,6 +860,15 @@ impl App {
                     return Ok(());
                 }
 
+                if self.save_conflict.is_some() {
+                    self.handle_conflict_keys(key.code);
+                    return Ok(());
+                }
+                if self.export_menu {
+                    self.handle_export_menu_keys(key.code);
+                    return Ok(());
+                }
+
                 match self.screen {
                     Screen::DeckSelect => self.handle_deck_select_keys(key.code),
                     Screen::Study => self.handle_study_keys(key.code),
@@ -384,6 +876,9 @@ impl App {
                     Screen::CardBrowser => self.handle_card_browser_keys(key.code),
                     Screen::Stats => self.handle_stats_keys(key.code),
                     Screen::Complete => self.handle_complete_keys(key.code),
+                    Screen::Backups => self.handle_backups_keys(key.code),
+                    Screen::SyncConflicts => self.handle_sync_conflict_keys(key.code),
+                    Screen::Import => self.handle_import_keys(key.code),
                 }
             }
         }
@@ -391,6 +886,11 @@ impl App {
     }
 
     fn handle_deck_select_keys(&mut self, key: KeyCode) {
+        if self.broken_focus {
+            self.handle_broken_deck_keys(key);
+            return;
+        }
+
         match key {
             KeyCode::Char('q') | KeyCode::Esc => self.running = false,
             KeyCode::Char('t') => self.cycle_theme(),
@@ -428,11 +928,110 @@ impl App {
             KeyCode::Char('b') => {
                 self.browse_selected_deck();
             }
-            KeyCode::Char('x') => {
-                self.export_backup();
+            KeyCode::Char(' ') => {
+                self.toggle_deck_mark();
+            }
+            KeyCode::Char('x') if !self.deck_list.is_empty() => {
+                self.export_menu = true;
+            }
+            KeyCode::Char('i') => {
+                let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+                self.import_picker = Some(FilePicker::new(home, IMPORT_EXTENSIONS));
+                self.import_wizard = None;
+                self.screen = Screen::Import;
             }
             KeyCode::Char('s') => {
-                self.screen = Screen::Stats;
+                self.open_stats();
+            }
+            KeyCode::Char('B') => {
+                self.open_backups();
+            }
+            KeyCode::Char('S') => {
+                self.sync_now();
+            }
+            KeyCode::Char('w') if !self.broken_decks.is_empty() => {
+                self.broken_focus = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_export_menu_keys(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('a') => self.export_decks(ExportFormat::AnkiPackage),
+            KeyCode::Char('j') => self.export_decks(ExportFormat::Backup),
+            KeyCode::Char('c') => self.export_decks(ExportFormat::Csv),
+            KeyCode::Char('m') => self.export_decks(ExportFormat::Markdown),
+            KeyCode::Esc | KeyCode::Char('q') => self.export_menu = false,
+            _ => {}
+        }
+    }
+
+    fn handle_import_keys(&mut self, key: KeyCode) {
+        if let Some(ref mut wizard) = self.import_wizard {
+            match wizard.handle_key(key, &self.storage) {
+                WizardAction::None => {}
+                // Back to the picker, where the file was chosen
+                WizardAction::Cancel => self.import_wizard = None,
+                WizardAction::Done(message) => {
+                    self.close_import();
+                    self.refresh_deck_list();
+                    self.set_status(message);
+                }
+            }
+            return;
+        }
+
+        let Some(ref mut picker) = self.import_picker else {
+            self.close_import();
+            return;
+        };
+        match picker.handle_key(key) {
+            PickerAction::None => {}
+            PickerAction::Cancel => self.close_import(),
+            PickerAction::Open(path) if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json")) => {
+                self.close_import();
+                self.import_backup(&path, RestoreMode::Skip);
+            }
+            PickerAction::Open(path) => {
+                self.import_wizard = Some(ImportWizard::for_file(&path, &self.storage));
+            }
+        }
+    }
+
+    fn close_import(&mut self) {
+        self.import_picker = None;
+        self.import_wizard = None;
+        self.screen = Screen::DeckSelect;
+    }
+
+    fn handle_broken_deck_keys(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc | KeyCode::Char('w') | KeyCode::Char('q') => {
+                self.broken_focus = false;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let i = self.broken_list_state.selected().unwrap_or(0);
+                let new_i = if i == 0 {
+                    self.broken_decks.len().saturating_sub(1)
+                } else {
+                    i - 1
+                };
+                self.broken_list_state.select(Some(new_i));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let i = self.broken_list_state.selected().unwrap_or(0);
+                let new_i = if i >= self.broken_decks.len().saturating_sub(1) {
+                    0
+                } else {
+                    i + 1
+                };
+                self.broken_list_state.select(Some(new_i));
+            }
+            KeyCode::Char('m') => self.quarantine_selected_broken_deck(),
+            KeyCode::Char('o') | KeyCode::Enter => self.repair_selected_broken_deck(),
+            KeyCode::Char('r') => {
+                self.refresh_deck_list();
             }
             _ => {}
         }
@@ -463,6 +1062,7 @@ impl App {
             KeyCode::Char('b') => {
                 self.enter_card_browser();
             }
+            KeyCode::Char('o') => self.open_card_media(),
             _ => {}
         }
     }
@@ -520,10 +1120,73 @@ impl App {
         }
     }
 
+    fn handle_backups_keys(&mut self, key: KeyCode) {
+        // Any key other than a second 'o' cancels a pending overwrite
+        let overwrite_confirmed = self.backup_overwrite_pending && key == KeyCode::Char('o');
+        self.backup_overwrite_pending = false;
+
+        match key {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.screen = Screen::DeckSelect;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let i = self.backup_list_state.selected().unwrap_or(0);
+                self.backup_list_state.select(Some(i.saturating_sub(1)));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let i = self.backup_list_state.selected().unwrap_or(0);
+                let last = self.backup_list.len().saturating_sub(1);
+                self.backup_list_state.select(Some((i + 1).min(last)));
+            }
+            KeyCode::Enter | KeyCode::Char('r') => {
+                self.restore_selected_backup(RestoreMode::Skip);
+            }
+            KeyCode::Char('m') => {
+                self.restore_selected_backup(RestoreMode::Merge);
+            }
+            KeyCode::Char('n') => {
+                self.restore_selected_backup(RestoreMode::Rename);
+            }
+            KeyCode::Char('o') if overwrite_confirmed => {
+                self.restore_selected_backup(RestoreMode::Overwrite);
+            }
+            KeyCode::Char('o') if !self.backup_list.is_empty() => {
+                self.backup_overwrite_pending = true;
+                self.set_status("Press o again to replace existing decks with this backup".to_string());
+            }
+            KeyCode::Char('c') => {
+                self.create_backup_now();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_sync_conflict_keys(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.sync_conflicts.clear();
+                self.screen = Screen::DeckSelect;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let i = self.sync_conflict_state.selected().unwrap_or(0);
+                self.sync_conflict_state.select(Some(i.saturating_sub(1)));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let i = self.sync_conflict_state.selected().unwrap_or(0);
+                let last = self.sync_conflicts.len().saturating_sub(1);
+                self.sync_conflict_state.select(Some((i + 1).min(last)));
+            }
+            KeyCode::Enter | KeyCode::Char('m') => self.resolve_sync_conflict(false),
+            KeyCode::Char('t') => self.resolve_sync_conflict(true),
+            _ => {}
+        }
+    }
+
     fn handle_stats_keys(&mut self, key: KeyCode) {
         match key {
             KeyCode::Esc | KeyCode::Char('q') => {
                 self.screen = Screen::DeckSelect;
+                self.collection_stats = None;
             }
             KeyCode::Char('t') => self.cycle_theme(),
             _ => {}
@@ -663,6 +1326,10 @@ impl App {
                     self.card_delete_pending = false;
                     self.screen = Screen::AddCard;
                 }
+                KeyCode::Char('u') => {
+                    self.card_delete_pending = false;
+                    self.toggle_suspend_selected_card();
+                }
                 KeyCode::Char('t') => {
                     self.card_delete_pending = false;
                     self.cycle_theme();
@@ -695,9 +1362,128 @@ impl App {
             Screen::CardBrowser => self.render_card_browser(frame, area),
             Screen::Stats => self.render_stats(frame, area),
             Screen::Complete => self.render_complete(frame, area),
+            Screen::Backups => self.render_backups(frame, area),
+            Screen::SyncConflicts => self.render_sync_conflicts(frame, area),
+            Screen::Import => {
+                if let Some(ref wizard) = self.import_wizard {
+                    wizard.render(frame, area, &self.theme);
+                } else if let Some(ref mut picker) = self.import_picker {
+                    picker.render(frame, area, &self.theme);
+                }
+            }
+        }
+
+        if self.save_conflict.is_some() {
+            self.render_conflict(frame, area);
+        } else if self.export_menu {
+            self.render_export_menu(frame, area);
         }
     }
 
+    fn render_export_menu(&self, frame: &mut Frame, area: Rect) {
+        let marked = self.marked_deck_ids().len();
+        let title = if marked == 0 {
+            " Export all decks ".to_string()
+        } else {
+            format!(" Export {} marked decks ", marked)
+        };
+        let popup = centered_rect(50, 30, area);
+        let accent = self.theme.colors.primary;
+        let key_style = Style::default().fg(accent).add_modifier(Modifier::BOLD);
+        let text_style = Style::default().fg(self.theme.colors.text);
+
+        let lines = vec![
+            Line::from(vec![
+                Span::styled("a", key_style),
+                Span::styled("  Anki package (.apkg) with scheduling and media", text_style),
+            ]),
+            Line::from(vec![
+                Span::styled("j", key_style),
+                Span::styled("  srl backup (.json)", text_style),
+            ]),
+            Line::from(vec![
+                Span::styled("c", key_style),
+                Span::styled("  CSV with card text, tags and scheduling (.csv)", text_style),
+            ]),
+            Line::from(vec![
+                Span::styled("m", key_style),
+                Span::styled("  Markdown, a file per deck, without scheduling (.md)", text_style),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Files go to your documents folder. Space marks decks in the list.",
+                Style::default().fg(self.theme.colors.text_muted),
+            )),
+            Line::from(vec![
+                Span::styled("Esc", key_style),
+                Span::styled(" cancel", text_style),
+            ]),
+        ];
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(ratatui::widgets::Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .border_style(Style::default().fg(accent))
+                        .title(title)
+                        .title_style(self.theme.highlight())
+                        .style(Style::default().bg(self.theme.colors.bg_elevated)),
+                ),
+            popup,
+        );
+    }
+
+    fn render_conflict(&self, frame: &mut Frame, area: Rect) {
+        let Some(ref conflict) = self.save_conflict else {
+            return;
+        };
+        let popup = centered_rect(60, 30, area);
+        let warning_color = self.theme.colors.rating_hard;
+        let key_style = Style::default().fg(self.theme.colors.primary).add_modifier(Modifier::BOLD);
+        let text_style = Style::default().fg(self.theme.colors.text);
+
+        let lines = vec![
+            Line::from(Span::styled(conflict.to_string(), text_style)),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("r", key_style),
+                Span::styled("  reload it, discarding the change just made here", text_style),
+            ]),
+            Line::from(vec![
+                Span::styled("m", key_style),
+                Span::styled("  merge both sets of changes card by card", text_style),
+            ]),
+            Line::from(vec![
+                Span::styled("o", key_style),
+                Span::styled("  overwrite it with this copy", text_style),
+            ]),
+            Line::from(vec![
+                Span::styled("Esc", key_style),
+                Span::styled(" decide later (nothing is saved)", text_style),
+            ]),
+        ];
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(ratatui::widgets::Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .border_style(Style::default().fg(warning_color))
+                        .title(" ⚠ Deck changed on disk ")
+                        .title_style(Style::default().fg(warning_color))
+                        .style(Style::default().bg(self.theme.colors.bg_elevated)),
+                ),
+            popup,
+        );
+    }
+
     fn render_deck_select(&mut self, frame: &mut Frame, area: Rect) {
         let chunks = Layout::vertical([
             Constraint::Length(2),   // Top padding
@@ -711,20 +1497,49 @@ impl App {
         // Logo
         Logo::render_to(&self.theme, chunks[1], frame.buffer_mut());
 
-        // Deck list
+        // Deck list, with a warning panel underneath when some deck files are unreadable
         let list_area = centered_rect(60, 100, chunks[3]);
+        let (list_area, warning_area) = if self.broken_decks.is_empty() {
+            (list_area, None)
+        } else {
+            let warning_height = (self.broken_decks.len().min(4) as u16) * 2 + 2;
+            let split = Layout::vertical([
+                Constraint::Min(3),
+                Constraint::Length(warning_height),
+            ])
+            .split(list_area);
+            (split[0], Some(split[1]))
+        };
 
         let items: Vec<ListItem> = self
             .deck_list
             .iter()
             .map(|deck| {
-                let content = Line::from(vec![
+                let mut spans = Vec::new();
+                if !self.marked_decks.is_empty() {
+                    let mark = if self.marked_decks.contains(&deck.id) { "✓ " } else { "  " };
+                    spans.push(Span::styled(mark, Style::default().fg(self.theme.colors.success)));
+                }
+                spans.extend([
                     Span::styled(&deck.name, Style::default().add_modifier(Modifier::BOLD)),
                     Span::styled(
                         format!(" ({} cards)", deck.card_count),
                         Style::default().fg(self.theme.colors.text_muted),
                     ),
                 ]);
+                if deck.due_count > 0 {
+                    spans.push(Span::styled(
+                        format!(" {} due", deck.due_count),
+                        self.theme.stats_due(),
+                    ));
+                }
+                if deck.new_count > 0 {
+                    spans.push(Span::styled(
+                        format!(" {} new", deck.new_count),
+                        self.theme.stats_new(),
+                    ));
+                }
+                let content = Line::from(spans);
                 ListItem::new(content)
             })
             .collect();
@@ -743,33 +1558,58 @@ impl App {
 
         frame.render_stateful_widget(list, list_area, &mut self.deck_list_state);
 
+        if let Some(warning_area) = warning_area {
+            self.render_broken_decks(frame, warning_area);
+        }
+
         // Key hints with theme indicator
         let theme_hint = format!("[{}]", self.theme.name.display_name());
-        let hints_data: [(&str, &str); 9] = [
-            ("j/k", "nav"),
-            ("Enter", "study"),
-            ("b", "browse"),
-            ("n", "new"),
-            ("d", "del"),
-            ("x", "export"),
-            ("s", "stats"),
-            ("t", &theme_hint),
-            ("q", "quit"),
-        ];
+        let hints_data: Vec<(&str, &str)> = if self.broken_focus {
+            vec![
+                ("j/k", "nav"),
+                ("o", "open to repair"),
+                ("m", "move to quarantine"),
+                ("r", "rescan"),
+                ("Esc", "back"),
+            ]
+        } else {
+            let mut data = vec![
+                ("j/k", "nav"),
+                ("Enter", "study"),
+                ("b", "browse"),
+                ("n", "new"),
+                ("d", "del"),
+                ("Space", "mark"),
+                ("x", "export"),
+                ("i", "import"),
+                ("B", "backups"),
+                ("S", "sync"),
+                ("s", "stats"),
+                ("t", theme_hint.as_str()),
+                ("q", "quit"),
+            ];
+            if !self.broken_decks.is_empty() {
+                data.insert(11, ("w", "warnings"));
+            }
+            data
+        };
         let hints = KeyHints::new(&hints_data, &self.theme);
         frame.render_widget(hints, chunks[4]);
 
-        // Show status message if recent (within 5 seconds)
+        self.render_status(frame, chunks[4]);
+    }
+
+    /// Show the status message, if recent (within 5 seconds), just above the hints.
+    fn render_status(&self, frame: &mut Frame, hints_area: Rect) {
         if let Some((ref msg, time)) = self.status_message {
             if time.elapsed().as_secs() < 5 {
                 let status = Paragraph::new(msg.as_str())
                     .alignment(Alignment::Center)
                     .style(Style::default().fg(self.theme.colors.success));
-                // Render above the hints
                 let status_area = Rect {
-                    x: chunks[4].x,
-                    y: chunks[4].y.saturating_sub(1),
-                    width: chunks[4].width,
+                    x: hints_area.x,
+                    y: hints_area.y.saturating_sub(1),
+                    width: hints_area.width,
                     height: 1,
                 };
                 frame.render_widget(status, status_area);
@@ -777,6 +1617,194 @@ impl App {
         }
     }
 
+    fn render_backups(&mut self, frame: &mut Frame, area: Rect) {
+        let chunks = Layout::vertical([
+            Constraint::Length(2),   // Top padding
+            Constraint::Length(2),   // Backup directory
+            Constraint::Min(5),      // Backup list
+            Constraint::Length(3),   // Help
+        ])
+        .split(area);
+
+        let dir = Paragraph::new(format!("Automatic backups in {}", self.backup_rotation.dir().display()))
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(self.theme.colors.text_muted));
+        frame.render_widget(dir, chunks[1]);
+
+        let now = chrono::Local::now();
+        let items: Vec<ListItem> = if self.backup_list.is_empty() {
+            vec![ListItem::new(Span::styled(
+                "No automatic backups yet - press c to create one",
+                Style::default().fg(self.theme.colors.text_dim),
+            ))]
+        } else {
+            self.backup_list
+                .iter()
+                .map(|backup| {
+                    let decks = match backup.deck_count {
+                        Some(n) => format!("{} decks", n),
+                        None if backup.encrypted => "encrypted".to_string(),
+                        None => "unreadable".to_string(),
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(
+                            backup.created_at.format("%Y-%m-%d %H:%M").to_string(),
+                            Style::default().add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(
+                            format!("  {} ago", format_age(now - backup.created_at)),
+                            Style::default().fg(self.theme.colors.text_muted),
+                        ),
+                        Span::styled(
+                            format!("  {}, {:.1} KB", decks, backup.size as f64 / 1024.0),
+                            Style::default().fg(self.theme.colors.text_dim),
+                        ),
+                    ]))
+                })
+                .collect()
+        };
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(self.theme.colors.primary))
+                    .title(" Backups ")
+                    .title_style(self.theme.highlight()),
+            )
+            .highlight_style(self.theme.selected())
+            .highlight_symbol("> ");
+
+        let list_area = centered_rect(60, 100, chunks[2]);
+        frame.render_stateful_widget(list, list_area, &mut self.backup_list_state);
+
+        let hints = KeyHints::new(
+            &[
+                ("j/k", "nav"),
+                ("Enter", "restore new"),
+                ("m", "merge"),
+                ("o", "overwrite"),
+                ("n", "as copies"),
+                ("c", "back up now"),
+                ("Esc", "back"),
+            ],
+            &self.theme,
+        );
+        frame.render_widget(hints, chunks[3]);
+        self.render_status(frame, chunks[3]);
+    }
+
+    fn render_sync_conflicts(&mut self, frame: &mut Frame, area: Rect) {
+        let chunks = Layout::vertical([
+            Constraint::Length(2),   // Top padding
+            Constraint::Length(2),   // Explanation
+            Constraint::Min(5),      // Conflict list
+            Constraint::Length(3),   // Help
+        ])
+        .split(area);
+
+        let intro = Paragraph::new("These cards were edited on both sides. Your text was kept.")
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(self.theme.colors.text_muted));
+        frame.render_widget(intro, chunks[1]);
+
+        let label_style = Style::default().fg(self.theme.colors.text_dim);
+        let card_text = |card: &crate::models::Card| format!("{} / {}", card.front, card.back);
+        let items: Vec<ListItem> = self
+            .sync_conflicts
+            .iter()
+            .map(|conflict| {
+                ListItem::new(vec![
+                    Line::from(Span::styled(
+                        conflict.deck_name.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )),
+                    Line::from(vec![
+                        Span::styled("  mine:   ", label_style),
+                        Span::raw(card_text(&conflict.ours)),
+                    ]),
+                    Line::from(vec![
+                        Span::styled("  theirs: ", label_style),
+                        Span::raw(card_text(&conflict.theirs)),
+                    ]),
+                ])
+            })
+            .collect();
+
+        let warning_color = self.theme.colors.rating_hard;
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(warning_color))
+                    .title(format!(" Sync conflicts ({}) ", self.sync_conflicts.len()))
+                    .title_style(Style::default().fg(warning_color)),
+            )
+            .highlight_style(self.theme.selected())
+            .highlight_symbol("> ");
+
+        let list_area = centered_rect(70, 100, chunks[2]);
+        frame.render_stateful_widget(list, list_area, &mut self.sync_conflict_state);
+
+        let hints = KeyHints::new(
+            &[("j/k", "nav"), ("Enter", "keep mine"), ("t", "take theirs"), ("Esc", "back")],
+            &self.theme,
+        );
+        frame.render_widget(hints, chunks[3]);
+        self.render_status(frame, chunks[3]);
+    }
+
+    fn render_broken_decks(&mut self, frame: &mut Frame, area: Rect) {
+        let warning_color = self.theme.colors.rating_hard;
+        let items: Vec<ListItem> = self
+            .broken_decks
+            .iter()
+            .map(|broken| {
+                ListItem::new(vec![
+                    Line::from(Span::styled(
+                        file_label(&broken.path),
+                        Style::default().fg(warning_color).add_modifier(Modifier::BOLD),
+                    )),
+                    Line::from(Span::styled(
+                        format!("  {}", broken.error),
+                        Style::default().fg(self.theme.colors.text_muted),
+                    )),
+                ])
+            })
+            .collect();
+
+        let border_color = if self.broken_focus {
+            warning_color
+        } else {
+            self.theme.colors.text_dim
+        };
+        let title = format!(
+            " ⚠ {} unreadable deck file{} ",
+            self.broken_decks.len(),
+            if self.broken_decks.len() == 1 { "" } else { "s" }
+        );
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(border_color))
+                    .title(title)
+                    .title_style(Style::default().fg(warning_color)),
+            )
+            .highlight_style(if self.broken_focus {
+                self.theme.selected()
+            } else {
+                Style::default()
+            })
+            .highlight_symbol(if self.broken_focus { "> " } else { "  " });
+
+        frame.render_stateful_widget(list, area, &mut self.broken_list_state);
+    }
+
     fn render_study(&mut self, frame: &mut Frame, area: Rect) {
         let chunks = Layout::vertical([
             Constraint::Length(3),   // Header
@@ -827,23 +1855,26 @@ impl App {
         );
 
         // Key hints
-        let hints = if self.answer_revealed {
-            KeyHints::new(&[
+        let mut hints_data: Vec<(&str, &str)> = if self.answer_revealed {
+            vec![
                 ("Space", "flip"),
                 ("1", "Again"),
                 ("2", "Hard"),
                 ("3", "Good"),
                 ("4", "Easy"),
-                ("Esc", "quit"),
-            ], &self.theme)
+            ]
         } else {
-            KeyHints::new(&[
+            vec![
                 ("Space", "show answer"),
                 ("a", "add"),
                 ("b", "browse"),
-                ("Esc", "quit"),
-            ], &self.theme)
+            ]
         };
+        if !self.shown_media().is_empty() {
+            hints_data.push(("o", "open media"));
+        }
+        hints_data.push(("Esc", "quit"));
+        let hints = KeyHints::new(&hints_data, &self.theme);
         frame.render_widget(hints, chunks[6]);
     }
 
@@ -960,7 +1991,11 @@ impl App {
                 .iter()
                 .map(|card| {
                     let front_preview: String = card.front.trim_matches('"').trim().chars().take(25).collect();
-                    let status = if card.is_new() {
+                    let status = if card.suspended {
+                        "(suspended)".to_string()
+                    } else if card.is_held() {
+                        "(buried)".to_string()
+                    } else if card.is_new() {
                         "(new)".to_string()
                     } else if card.is_due() {
                         "(due)".to_string()
@@ -1022,6 +2057,7 @@ impl App {
                 ("j/k", "nav"),
                 ("e", "edit"),
                 ("d", "delete"),
+                ("u", "suspend"),
                 ("a", "add"),
                 ("Esc", "back"),
             ], &self.theme)
@@ -1200,41 +2236,20 @@ impl App {
             .style(self.theme.title());
         frame.render_widget(title, chunks[0]);
 
-        // Calculate aggregate stats from all decks
-        let mut total_reviews: u32 = 0;
-        let mut total_cards: usize = 0;
-        let mut review_dates: Vec<chrono::NaiveDate> = Vec::new();
-        let mut ease_counts = EaseLevelCounts::default();
-
-        for deck_info in &self.deck_list {
-            if let Ok(Some(deck)) = self.storage.load_deck(&deck_info.id) {
-                for card in &deck.cards {
-                    total_cards += 1;
-                    total_reviews += card.total_reviews;
-
-                    // Collect review dates for streak calculation
-                    if let Some(reviewed) = card.last_reviewed {
-                        review_dates.push(reviewed.date_naive());
-                    }
-
-                    // Categorize by ease factor
-                    if card.is_new() {
-                        ease_counts.new += 1;
-                    } else if card.ease_factor >= 2.5 {
-                        ease_counts.easy += 1;
-                    } else if card.ease_factor >= 2.0 {
-                        ease_counts.good += 1;
-                    } else if card.ease_factor >= 1.5 {
-                        ease_counts.hard += 1;
-                    } else {
-                        ease_counts.struggling += 1;
-                    }
-                }
-            }
+        // Aggregate stats are computed when the screen is opened, not every frame
+        if self.collection_stats.is_none() {
+            self.collection_stats = Some(CollectionStats::compute(&self.storage, &self.deck_list));
         }
-
-        // Calculate streaks
-        let (daily_streak, weekly_streak) = calculate_streaks(&review_dates);
+        let Some(stats) = self.collection_stats.as_ref() else {
+            return;
+        };
+        let CollectionStats {
+            total_cards,
+            total_reviews,
+            daily_streak,
+            weekly_streak,
+            ref ease_counts,
+        } = *stats;
 
         // Main content area
         let content_area = centered_rect(70, 100, chunks[2]);
@@ -1367,6 +2382,83 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     .split(popup_layout[1])[1]
 }
 
+/// Rough age like "5 min", "3 hours", or "2 days".
+fn format_age(age: chrono::Duration) -> String {
+    if age.num_days() > 0 {
+        format!("{} days", age.num_days())
+    } else if age.num_hours() > 0 {
+        format!("{} hours", age.num_hours())
+    } else {
+        format!("{} min", age.num_minutes().max(0))
+    }
+}
+
+/// Short display name for a file path.
+fn file_label(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Aggregate statistics across all decks, shown on the stats screen.
+pub struct CollectionStats {
+    total_cards: usize,
+    total_reviews: u32,
+    daily_streak: u32,
+    weekly_streak: u32,
+    ease_counts: EaseLevelCounts,
+}
+
+impl CollectionStats {
+    /// Load every deck once and aggregate its cards.
+    fn compute(storage: &DeckStorage, deck_list: &[DeckInfo]) -> Self {
+        let mut total_reviews: u32 = 0;
+        let mut total_cards: usize = 0;
+        let mut review_dates: Vec<chrono::NaiveDate> = Vec::new();
+        let mut ease_counts = EaseLevelCounts::default();
+
+        for deck_info in deck_list {
+            if let Ok(Some(deck)) = storage.load_deck(&deck_info.id) {
+                for card in &deck.cards {
+                    total_cards += 1;
+                    total_reviews += card.total_reviews;
+
+                    // Collect review dates for streak calculation; cards
+                    // reviewed before the log was kept only have the latest
+                    review_dates.extend(card.reviews.iter().map(|r| r.reviewed_at.date_naive()));
+                    if let Some(reviewed) = card.last_reviewed.filter(|_| card.reviews.is_empty()) {
+                        review_dates.push(reviewed.date_naive());
+                    }
+
+                    // Categorize by ease factor
+                    if card.is_new() {
+                        ease_counts.new += 1;
+                    } else if card.ease_factor >= 2.5 {
+                        ease_counts.easy += 1;
+                    } else if card.ease_factor >= 2.0 {
+                        ease_counts.good += 1;
+                    } else if card.ease_factor >= 1.5 {
+                        ease_counts.hard += 1;
+                    } else {
+                        ease_counts.struggling += 1;
+                    }
+                }
+            }
+        }
+
+        // Calculate streaks
+        let (daily_streak, weekly_streak) = calculate_streaks(&review_dates);
+
+        Self {
+            total_cards,
+            total_reviews,
+            daily_streak,
+            weekly_streak,
+            ease_counts,
+        }
+    }
+}
+
 /// Counts of cards at each ease level.
 #[derive(Default)]
 struct EaseLevelCounts {
@@ -1377,6 +2469,28 @@ struct EaseLevelCounts {
     struggling: usize,
 }
 
+/// Open a file with the desktop's default application, without waiting
+/// for it.
+fn open_externally(path: &Path) -> anyhow::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // Not `cmd /C start`: cmd would run `&` or `|` in a media name as commands
+        Command::new("explorer")
+    } else {
+        Command::new("xdg-open")
+    };
+    // Output would draw over the TUI
+    command
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    Ok(())
+}
+
 /// Calculate daily and weekly streaks from review dates.
 fn calculate_streaks(review_dates: &[chrono::NaiveDate]) -> (u32, u32) {
     use chrono::Datelike;
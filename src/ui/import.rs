@@ -0,0 +1,405 @@
+//! Import wizard: read a file, preview the cards in it and what importing
+//! would do, adjust the deck name and CSV column mapping, then import.
+
+use std::path::{Path, PathBuf};
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{block::BorderType, Block, Borders, Cell, Paragraph, Row, Table},
+    Frame,
+};
+
+use super::theme::Theme;
+use super::widgets::KeyHints;
+use crate::formats::{
+    save_imported, AnkiImporter, AnkiMedia, CsvColumn, CsvImporter, DeckImporter, ImportMode, ImportOptions,
+    MarkdownImporter, Saved,
+};
+use crate::models::Deck;
+use crate::storage::DeckStorage;
+
+/// Files the import screen's picker offers. JSON backups are restored
+/// directly; the rest open in the wizard.
+pub const IMPORT_EXTENSIONS: &[&str] = &["csv", "tsv", "txt", "md", "apkg", "colpkg", "json"];
+
+/// Input the wizard's keys go to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Path,
+    DeckName,
+    Columns,
+    Mode,
+}
+
+impl Field {
+    const ORDER: [Field; 4] = [Field::Path, Field::DeckName, Field::Columns, Field::Mode];
+
+    fn step(self, by: isize) -> Self {
+        let i = Self::ORDER.iter().position(|f| *f == self).unwrap_or(0) as isize;
+        Self::ORDER[(i + by).rem_euclid(Self::ORDER.len() as isize) as usize]
+    }
+}
+
+/// A file read for import, and what importing it would do.
+struct Preview {
+    format: &'static str,
+    decks: Vec<Deck>,
+    /// Rows the file's parser left out, and why.
+    skipped: Vec<String>,
+    media: Option<AnkiMedia>,
+    /// What saving each deck would do, in the same order.
+    plans: Vec<Saved>,
+}
+
+/// What the app should do after a key press in the wizard.
+pub enum WizardAction {
+    None,
+    Cancel,
+    /// Imported; the message says what was done.
+    Done(String),
+}
+
+pub struct ImportWizard {
+    path: String,
+    /// Deck name for formats without their own; empty to name it after the file.
+    deck_name: String,
+    /// CSV column mapping like `front,back,-,tags`; empty to use the header.
+    columns: String,
+    mode: ImportMode,
+    focus: Field,
+    preview: Option<Preview>,
+    /// Fields changed since the preview was made.
+    stale: bool,
+    error: Option<String>,
+}
+
+impl ImportWizard {
+    /// Open the wizard on `path`, previewing it straight away.
+    pub fn for_file(path: &Path, storage: &DeckStorage) -> Self {
+        let mut wizard = Self {
+            path: path.display().to_string(),
+            deck_name: String::new(),
+            columns: String::new(),
+            mode: ImportMode::Skip,
+            focus: Field::DeckName,
+            preview: None,
+            stale: true,
+            error: None,
+        };
+        wizard.load(storage);
+        wizard
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode, storage: &DeckStorage) -> WizardAction {
+        match key {
+            KeyCode::Esc => return WizardAction::Cancel,
+            KeyCode::Tab | KeyCode::Down => self.focus = self.focus.step(1),
+            KeyCode::BackTab | KeyCode::Up => self.focus = self.focus.step(-1),
+            KeyCode::Enter if self.stale || self.preview.is_none() => self.load(storage),
+            KeyCode::Enter => return self.import(storage),
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') if self.focus == Field::Mode => {
+                self.mode = match self.mode {
+                    ImportMode::Skip => ImportMode::Update,
+                    ImportMode::Update => ImportMode::Skip,
+                };
+                self.stale = true;
+            }
+            KeyCode::Char(c) => {
+                if let Some(field) = self.focused_text() {
+                    field.push(c);
+                    self.stale = true;
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(field) = self.focused_text() {
+                    field.pop();
+                    self.stale = true;
+                }
+            }
+            _ => {}
+        }
+        WizardAction::None
+    }
+
+    fn focused_text(&mut self) -> Option<&mut String> {
+        match self.focus {
+            Field::Path => Some(&mut self.path),
+            Field::DeckName => Some(&mut self.deck_name),
+            Field::Columns => Some(&mut self.columns),
+            Field::Mode => None,
+        }
+    }
+
+    /// The path typed in, with `~` standing for the home directory.
+    fn file_path(&self) -> PathBuf {
+        let path = self.path.trim();
+        match (path.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(path),
+        }
+    }
+
+    fn options(&self, dry_run: bool) -> ImportOptions {
+        ImportOptions {
+            mode: self.mode,
+            remove_missing: false,
+            dry_run,
+        }
+    }
+
+    /// Read the file and work out what importing it would do.
+    fn load(&mut self, storage: &DeckStorage) {
+        self.preview = None;
+        self.stale = false;
+        self.error = match self.read(storage) {
+            Ok(preview) => {
+                self.preview = Some(preview);
+                None
+            }
+            Err(e) => Some(format!("{:#}", e)),
+        };
+    }
+
+    fn read(&self, storage: &DeckStorage) -> anyhow::Result<Preview> {
+        if self.path.trim().is_empty() {
+            anyhow::bail!("Type the path of a .csv, .md, .apkg, .colpkg or .txt file");
+        }
+        let path = self.file_path();
+        if !path.is_file() {
+            anyhow::bail!("No file at {}", path.display());
+        }
+        let deck_name = Some(self.deck_name.trim()).filter(|name| !name.is_empty());
+        let extension = path.extension().and_then(|e| e.to_str()).map(str::to_lowercase);
+
+        let (format, decks, skipped, media) = if extension.as_deref() == Some("csv") {
+            let mut importer = CsvImporter::default();
+            if !self.columns.trim().is_empty() {
+                importer = importer.with_columns(CsvColumn::parse_list(&self.columns)?);
+            }
+            let imported = importer.import(&path, deck_name)?;
+            (importer.name(), imported.decks, imported.skipped, None)
+        } else if extension.as_deref() == Some("md") {
+            let imported = MarkdownImporter.import(&path, deck_name)?;
+            (MarkdownImporter.name(), imported.decks, imported.skipped, None)
+        } else {
+            let package = AnkiImporter.import_package(&path, deck_name)?;
+            (AnkiImporter.name(), package.decks, Vec::new(), Some(package.media))
+        };
+
+        let options = self.options(true);
+        let plans = decks
+            .iter()
+            .map(|deck| save_imported(storage, deck.clone(), &options).map(|(_, saved)| saved))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Preview { format, decks, skipped, media, plans })
+    }
+
+    /// Save the previewed decks, and their media.
+    fn import(&mut self, storage: &DeckStorage) -> WizardAction {
+        let Some(preview) = self.preview.take() else {
+            return WizardAction::None;
+        };
+        let options = self.options(false);
+        let mut messages = Vec::new();
+
+        for deck in preview.decks {
+            let card_count = deck.cards.len();
+            let result = save_imported(storage, deck, &options).and_then(|(deck, saved)| {
+                if !matches!(saved, Saved::Skipped) {
+                    if let Some(ref media) = preview.media {
+                        media.save_for(&deck, storage)?;
+                    }
+                }
+                Ok((deck, saved))
+            });
+            match result {
+                Ok((deck, Saved::Created)) => messages.push(format!("Imported {} cards into '{}'", card_count, deck.name)),
+                Ok((deck, Saved::Updated(changes))) => messages.push(format!("Updated '{}': {}", deck.name, changes)),
+                Ok((deck, Saved::Skipped)) => messages.push(format!("Skipped '{}'", deck.name)),
+                Err(e) => {
+                    // Decks saved so far stay; preview again to see where things stand
+                    self.stale = true;
+                    self.error = Some(format!("Import failed: {:#}", e));
+                    return WizardAction::None;
+                }
+            }
+        }
+        WizardAction::Done(messages.join("; "))
+    }
+
+    // ══════════════════════════════════════════════════════════════════════
+    // Rendering
+    // ══════════════════════════════════════════════════════════════════════
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let skipped_height = self.preview.as_ref().map_or(0, |p| p.skipped.len().min(4) as u16);
+        let chunks = Layout::vertical([
+            Constraint::Length(3),   // Title
+            Constraint::Length(3),   // Path
+            Constraint::Length(3),   // Deck name, columns, mode
+            Constraint::Length(3),   // Summary
+            Constraint::Min(3),      // Card preview
+            Constraint::Length(skipped_height),
+            Constraint::Length(2),   // Hints
+        ])
+        .split(Rect {
+            x: area.x + area.width / 10,
+            width: area.width - area.width / 5,
+            ..area
+        });
+
+        let title = Paragraph::new("Import")
+            .alignment(Alignment::Center)
+            .style(theme.title());
+        frame.render_widget(title, chunks[0]);
+
+        frame.render_widget(self.input(" File ", &self.path, Field::Path, theme), chunks[1]);
+        let row = Layout::horizontal([
+            Constraint::Percentage(40),
+            Constraint::Percentage(40),
+            Constraint::Percentage(20),
+        ])
+        .split(chunks[2]);
+        frame.render_widget(self.input(" Deck name (from file if empty) ", &self.deck_name, Field::DeckName, theme), row[0]);
+        frame.render_widget(self.input(" CSV columns (from header if empty) ", &self.columns, Field::Columns, theme), row[1]);
+        let mode = match self.mode {
+            ImportMode::Skip => "skip existing",
+            ImportMode::Update => "update existing",
+        };
+        frame.render_widget(self.input(" Mode ", mode, Field::Mode, theme), row[2]);
+
+        frame.render_widget(Paragraph::new(self.summary(theme)), chunks[3]);
+
+        if let Some(ref preview) = self.preview {
+            self.render_cards(frame, chunks[4], preview, theme);
+            let skipped: Vec<Line> = preview
+                .skipped
+                .iter()
+                .take(chunks[5].height as usize)
+                .map(|reason| Line::from(Span::styled(format!("Skipped {}", reason), Style::default().fg(theme.colors.warning))))
+                .collect();
+            frame.render_widget(Paragraph::new(skipped), chunks[5]);
+        }
+
+        let enter = if self.stale || self.preview.is_none() { "preview" } else { "import" };
+        let mut hints = vec![("Tab", "next field"), ("Enter", enter)];
+        if self.focus == Field::Mode {
+            hints.push(("Space", "change mode"));
+        }
+        hints.push(("Esc", "cancel"));
+        frame.render_widget(KeyHints::new(&hints, theme), chunks[6]);
+    }
+
+    fn input<'a>(&self, title: &'a str, text: &'a str, field: Field, theme: &Theme) -> Paragraph<'a> {
+        let style = if self.focus == field {
+            Style::default().fg(theme.colors.accent)
+        } else {
+            Style::default().fg(theme.colors.text_muted)
+        };
+        Paragraph::new(text)
+            .style(Style::default().fg(theme.colors.text))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(style)
+                    .title(title)
+                    .title_style(style),
+            )
+    }
+
+    /// What the file holds and what importing it does, or why it can't be read.
+    fn summary(&self, theme: &Theme) -> Vec<Line<'static>> {
+        let muted = Style::default().fg(theme.colors.text_muted);
+        if let Some(ref error) = self.error {
+            return vec![Line::from(Span::styled(error.clone(), Style::default().fg(theme.colors.error)))];
+        }
+        let Some(ref preview) = self.preview else {
+            return vec![Line::from(Span::styled("Press Enter to read the file", muted))];
+        };
+
+        let cards: usize = preview.decks.iter().map(|d| d.cards.len()).sum();
+        let mut lines = vec![Line::from(Span::styled(
+            format!(
+                "{} file: {} cards in {} decks, {} skipped",
+                preview.format,
+                cards,
+                preview.decks.len(),
+                preview.skipped.len()
+            ),
+            Style::default().fg(theme.colors.text),
+        ))];
+        for (deck, plan) in preview.decks.iter().zip(&preview.plans).take(2) {
+            let (text, color) = match plan {
+                Saved::Created => (format!("→ create '{}'", deck.name), theme.colors.success),
+                Saved::Updated(changes) => (format!("→ update '{}': {}", deck.name, changes), theme.colors.info),
+                Saved::Skipped => (
+                    format!("→ skip '{}': a deck of that name exists (change the mode to update it)", deck.name),
+                    theme.colors.warning,
+                ),
+            };
+            lines.push(Line::from(Span::styled(text, Style::default().fg(color))));
+        }
+        if preview.decks.len() > 2 {
+            lines.push(Line::from(Span::styled(format!("  and {} more decks", preview.decks.len() - 2), muted)));
+        }
+        if self.stale {
+            lines.push(Line::from(Span::styled("Changed; press Enter to preview again", muted)));
+        }
+        lines
+    }
+
+    fn render_cards(&self, frame: &mut Frame, area: Rect, preview: &Preview, theme: &Theme) {
+        let multiple_decks = preview.decks.len() > 1;
+        let rows: Vec<Row> = preview
+            .decks
+            .iter()
+            .flat_map(|deck| deck.cards.iter().map(move |card| (deck, card)))
+            .take(area.height.saturating_sub(3) as usize)
+            .map(|(deck, card)| {
+                let mut cells = vec![Cell::from(first_line(&card.front)), Cell::from(first_line(&card.back))];
+                cells.push(Cell::from(card.tags.join(" ")));
+                if multiple_decks {
+                    cells.push(Cell::from(deck.name.clone()));
+                }
+                Row::new(cells)
+            })
+            .collect();
+
+        let mut header = vec!["Front", "Back", "Tags"];
+        let mut widths = vec![Constraint::Percentage(40), Constraint::Percentage(40), Constraint::Percentage(20)];
+        if multiple_decks {
+            header.push("Deck");
+            widths = vec![
+                Constraint::Percentage(32),
+                Constraint::Percentage(32),
+                Constraint::Percentage(16),
+                Constraint::Percentage(20),
+            ];
+        }
+
+        let table = Table::new(rows, widths)
+            .header(Row::new(header).style(Style::default().fg(theme.colors.primary).add_modifier(Modifier::BOLD)))
+            .style(Style::default().fg(theme.colors.text))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(theme.colors.primary))
+                    .title(" Preview ")
+                    .title_style(theme.highlight()),
+            );
+        frame.render_widget(table, area);
+    }
+}
+
+/// First line of card text, marked when there's more.
+fn first_line(text: &str) -> String {
+    match text.split_once('\n') {
+        Some((first, _)) => format!("{} …", first),
+        None => text.to_string(),
+    }
+}
@@ -1,7 +1,11 @@
 //! TUI module for the flashcard application.
 
 mod app;
+mod import;
+mod picker;
 pub mod theme;
+mod unlock;
 mod widgets;
 
 pub use app::App;
+pub use unlock::prompt_passphrase;
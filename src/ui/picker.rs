@@ -0,0 +1,241 @@
+//! File browser for picking a file to import.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{block::BorderType, Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use super::theme::Theme;
+use super::widgets::KeyHints;
+
+struct Entry {
+    name: String,
+    path: PathBuf,
+    is_dir: bool,
+    /// File size in bytes; 0 for directories.
+    size: u64,
+}
+
+/// What the app should do after a key press in the picker.
+pub enum PickerAction {
+    None,
+    Cancel,
+    Open(PathBuf),
+}
+
+/// Lists a directory's subdirectories and the files with one of the
+/// given extensions.
+pub struct FilePicker {
+    dir: PathBuf,
+    extensions: &'static [&'static str],
+    show_hidden: bool,
+    /// `..` first when the directory has a parent, then directories, then files.
+    entries: Vec<Entry>,
+    state: ListState,
+    error: Option<String>,
+}
+
+impl FilePicker {
+    pub fn new(dir: PathBuf, extensions: &'static [&'static str]) -> Self {
+        let mut picker = Self {
+            dir: PathBuf::new(),
+            extensions,
+            show_hidden: false,
+            entries: Vec::new(),
+            state: ListState::default(),
+            error: None,
+        };
+        picker.open_dir(dir, None);
+        picker
+    }
+
+    /// Show `dir`, with `select` selected if it's in it.
+    fn open_dir(&mut self, dir: PathBuf, select: Option<&Path>) {
+        self.entries.clear();
+        self.error = None;
+        if let Some(parent) = dir.parent() {
+            self.entries.push(Entry {
+                name: "..".to_string(),
+                path: parent.to_path_buf(),
+                is_dir: true,
+                size: 0,
+            });
+        }
+        match self.read_entries(&dir) {
+            Ok(entries) => self.entries.extend(entries),
+            Err(e) => self.error = Some(format!("Could not read {}: {}", dir.display(), e)),
+        }
+
+        let selected = select
+            .and_then(|path| self.entries.iter().position(|e| e.path == path))
+            // Start on the first entry past `..`
+            .unwrap_or(usize::from(self.entries.len() > 1));
+        self.state = ListState::default().with_selected(Some(selected));
+        self.dir = dir;
+    }
+
+    fn read_entries(&self, dir: &Path) -> std::io::Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') && !self.show_hidden {
+                continue;
+            }
+            let path = entry.path();
+            // Follows symlinks, so linked directories can be entered
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let wanted = metadata.is_dir()
+                || path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| self.extensions.iter().any(|x| x.eq_ignore_ascii_case(e)));
+            if wanted {
+                entries.push(Entry {
+                    name,
+                    path,
+                    is_dir: metadata.is_dir(),
+                    size: if metadata.is_dir() { 0 } else { metadata.len() },
+                });
+            }
+        }
+        entries.sort_by_cached_key(|e| (!e.is_dir, e.name.to_lowercase()));
+        Ok(entries)
+    }
+
+    fn go_up(&mut self) {
+        if let Some(parent) = self.dir.parent().map(Path::to_path_buf) {
+            let from = self.dir.clone();
+            self.open_dir(parent, Some(&from));
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode) -> PickerAction {
+        match key {
+            KeyCode::Esc | KeyCode::Char('q') => return PickerAction::Cancel,
+            KeyCode::Up | KeyCode::Char('k') => {
+                let i = self.state.selected().unwrap_or(0);
+                self.state.select(Some(i.saturating_sub(1)));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let i = self.state.selected().unwrap_or(0);
+                let last = self.entries.len().saturating_sub(1);
+                self.state.select(Some((i + 1).min(last)));
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                let Some(entry) = self.state.selected().and_then(|i| self.entries.get(i)) else {
+                    return PickerAction::None;
+                };
+                if entry.name == ".." {
+                    self.go_up();
+                } else if entry.is_dir {
+                    self.open_dir(entry.path.clone(), None);
+                } else {
+                    return PickerAction::Open(entry.path.clone());
+                }
+            }
+            KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => self.go_up(),
+            KeyCode::Char('~') => {
+                if let Some(home) = dirs::home_dir() {
+                    self.open_dir(home, None);
+                }
+            }
+            KeyCode::Char('.') => {
+                self.show_hidden = !self.show_hidden;
+                let selected = self.state.selected().and_then(|i| self.entries.get(i)).map(|e| e.path.clone());
+                self.open_dir(self.dir.clone(), selected.as_deref());
+            }
+            _ => {}
+        }
+        PickerAction::None
+    }
+
+    // ══════════════════════════════════════════════════════════════════════
+    // Rendering
+    // ══════════════════════════════════════════════════════════════════════
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let chunks = Layout::vertical([
+            Constraint::Length(3),   // Title
+            Constraint::Length(2),   // Directory
+            Constraint::Min(5),      // Entries
+            Constraint::Length(3),   // Help
+        ])
+        .split(area);
+
+        let title = Paragraph::new("Import")
+            .alignment(Alignment::Center)
+            .style(theme.title());
+        frame.render_widget(title, chunks[0]);
+
+        let (text, color) = match self.error {
+            Some(ref error) => (error.clone(), theme.colors.error),
+            None => (
+                format!("{}  ({})", self.dir.display(), self.extensions.join(", ")),
+                theme.colors.text_muted,
+            ),
+        };
+        let dir = Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(color));
+        frame.render_widget(dir, chunks[1]);
+
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                if entry.is_dir {
+                    ListItem::new(Span::styled(format!("{}/", entry.name), Style::default().fg(theme.colors.primary)))
+                } else {
+                    ListItem::new(Line::from(vec![
+                        Span::styled(entry.name.clone(), Style::default().fg(theme.colors.text)),
+                        Span::styled(
+                            format!("  {:.1} KB", entry.size as f64 / 1024.0),
+                            Style::default().fg(theme.colors.text_dim),
+                        ),
+                    ]))
+                }
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(theme.colors.primary))
+                    .title(" Choose a file ")
+                    .title_style(theme.highlight()),
+            )
+            .highlight_style(theme.selected())
+            .highlight_symbol("> ");
+
+        let list_area = Rect {
+            x: chunks[2].x + chunks[2].width / 5,
+            width: chunks[2].width - chunks[2].width / 5 * 2,
+            ..chunks[2]
+        };
+        frame.render_stateful_widget(list, list_area, &mut self.state);
+
+        let hidden = if self.show_hidden { "hide hidden" } else { "show hidden" };
+        let hints_data = [
+            ("j/k", "nav"),
+            ("Enter", "open"),
+            ("Backspace", "up"),
+            ("~", "home"),
+            (".", hidden),
+            ("Esc", "back"),
+        ];
+        let hints = KeyHints::new(&hints_data, theme);
+        frame.render_widget(hints, chunks[3]);
+    }
+}
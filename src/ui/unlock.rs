@@ -0,0 +1,113 @@
+//! Passphrase prompt shown at startup when the decks are encrypted.
+
+use std::path::Path;
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{block::BorderType, Block, Borders, Paragraph},
+};
+use zeroize::Zeroizing;
+
+use super::theme::Theme;
+use super::widgets::{KeyHints, Logo};
+use crate::storage::Cipher;
+
+/// Ask for the passphrase of the encrypted decks in `dir` until it fits.
+/// Returns `None` if the user quits instead.
+pub fn prompt_passphrase<B: Backend>(
+    terminal: &mut Terminal<B>,
+    dir: &Path,
+    theme: &Theme,
+) -> Result<Option<Cipher>> {
+    // Reserved up front so typing doesn't reallocate and leave copies behind
+    let mut input = Zeroizing::new(String::with_capacity(256));
+    let mut error: Option<String> = None;
+
+    loop {
+        terminal.draw(|frame| render(frame, theme, input.chars().count(), false, error.as_deref()))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Enter if !input.is_empty() => {
+                // Deriving the key takes a moment
+                terminal.draw(|frame| render(frame, theme, input.chars().count(), true, None))?;
+
+                match Cipher::unlock(dir, &input) {
+                    Ok(cipher) => return Ok(Some(cipher)),
+                    Err(e) => {
+                        error = Some(format!("{:#}", e));
+                        input.clear();
+                    }
+                }
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+}
+
+fn render(frame: &mut Frame, theme: &Theme, typed: usize, unlocking: bool, error: Option<&str>) {
+    let area = frame.area();
+    frame.render_widget(
+        Block::default().style(Style::default().bg(theme.colors.bg_dark)),
+        area,
+    );
+
+    let width = area.width.min(50);
+    let column = Rect {
+        x: area.x + (area.width - width) / 2,
+        width,
+        ..area
+    };
+    let chunks = Layout::vertical([
+        Constraint::Min(1),     // Spacer
+        Constraint::Length(8),  // Logo
+        Constraint::Length(2),  // Spacing
+        Constraint::Length(3),  // Input
+        Constraint::Length(1),  // Message
+        Constraint::Length(1),  // Spacing
+        Constraint::Length(1),  // Hints
+        Constraint::Min(1),     // Spacer
+    ])
+    .split(column);
+
+    let mut logo_area = chunks[1];
+    logo_area.x = area.x;
+    logo_area.width = area.width;
+    frame.render_widget(Logo::new(theme), logo_area);
+
+    let accent = Style::default().fg(theme.colors.accent);
+    let input = Paragraph::new("•".repeat(typed))
+        .style(Style::default().fg(theme.colors.text))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(accent)
+                .title(" Passphrase ")
+                .title_style(accent),
+        );
+    frame.render_widget(input, chunks[3]);
+
+    let message = match error {
+        Some(error) => Span::styled(error, Style::default().fg(theme.colors.error)),
+        None if unlocking => Span::styled("Unlocking...", Style::default().fg(theme.colors.text_muted)),
+        None => Span::raw(""),
+    };
+    frame.render_widget(Paragraph::new(message).alignment(Alignment::Center), chunks[4]);
+
+    let hints = KeyHints::new(&[("Enter", "unlock"), ("Esc", "quit")], theme);
+    frame.render_widget(hints, chunks[6]);
+}
@@ -11,7 +11,8 @@ use textwrap::{wrap, Options, WordSplitter};
 use unicode_width::UnicodeWidthStr;
 
 use super::theme::Theme;
-use crate::models::DeckStats;
+use crate::markup;
+use crate::models::{media_refs, DeckStats, MediaKind};
 
 // ══════════════════════════════════════════════════════════════════════════
 // Logo Widget
@@ -200,7 +201,9 @@ impl Widget for FlashcardWidget<'_> {
         block.render(area, buf);
 
         // Trim leading/trailing quotation marks from content
-        let content = self.content.trim_matches('"').trim();
+        let trimmed = self.content.trim_matches('"').trim();
+        let (content, has_media) = media_placeholders(trimmed);
+        let content = content.as_str();
 
         // Build lines from content, handling explicit newlines
         // Cap width at 70 chars for readability on wide terminals
@@ -210,21 +213,52 @@ impl Widget for FlashcardWidget<'_> {
 
         if content_width > 0 {
             let options = Options::new(content_width).word_splitter(WordSplitter::NoHyphenation);
+            let code_style = Style::default().fg(self.theme.colors.accent);
+            let mut in_code_block = false;
+            // Code blocks and lists keep their indentation, so they're
+            // centered as a whole rather than line by line
+            let mut block_start: Option<usize> = None;
             // Split on explicit newlines first, then wrap each paragraph
             for paragraph in content.split('\n') {
-                if paragraph.is_empty() {
+                let in_block = in_code_block
+                    || markup::is_fence(paragraph)
+                    || markup::list_item(paragraph).is_some();
+                match block_start {
+                    None if in_block => block_start = Some(all_lines.len()),
+                    Some(start) if !in_block => {
+                        pad_block(&mut all_lines[start..]);
+                        block_start = None;
+                    }
+                    _ => {}
+                }
+
+                if markup::is_fence(paragraph) {
+                    in_code_block = !in_code_block;
+                } else if paragraph.is_empty() {
                     all_lines.push(Line::from(""));
+                } else if in_code_block {
+                    let segments = [(paragraph.to_string(), code_style)];
+                    all_lines.extend(wrap_styled(&segments, &options));
                 } else {
-                    let wrapped = wrap(paragraph, &options);
-                    for wrapped_line in wrapped {
-                        all_lines.push(Line::from(wrapped_line.into_owned()));
-                    }
+                    let segments = styled_segments(paragraph, code_style);
+                    all_lines.extend(wrap_styled(&segments, &options));
                 }
             }
+            if let Some(start) = block_start {
+                pad_block(&mut all_lines[start..]);
+            }
         } else {
             all_lines.push(Line::from(content.to_string()));
         }
 
+        if has_media {
+            all_lines.push(Line::from(""));
+            all_lines.push(Line::from(Span::styled(
+                "o: open media",
+                Style::default().fg(self.theme.colors.text_muted),
+            )));
+        }
+
         let estimated_lines = all_lines.len() as u16;
 
         // Content - use pre-computed lines for proper multiline support
@@ -253,6 +287,90 @@ impl Widget for FlashcardWidget<'_> {
     }
 }
 
+/// A line of card text as styled segments, with the markup rendered and
+/// list bullets drawn as such.
+fn styled_segments(line: &str, code_style: Style) -> Vec<(String, Style)> {
+    let mut segments = Vec::new();
+    let text = match markup::list_item(line) {
+        Some((marker, item)) => {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let bullet = if marker.starts_with(['-', '*']) { "• " } else { marker };
+            segments.push((format!("{}{}", indent, bullet), Style::default()));
+            item
+        }
+        None => line,
+    };
+
+    for run in markup::runs(text) {
+        let mut style = if run.code { code_style } else { Style::default() };
+        if run.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if run.italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        segments.push((run.text, style));
+    }
+    segments
+}
+
+/// Word-wrap styled segments as one paragraph, keeping their styles.
+fn wrap_styled(segments: &[(String, Style)], options: &Options) -> Vec<Line<'static>> {
+    let plain: String = segments.iter().map(|(text, _)| text.as_str()).collect();
+    let mut lines = Vec::new();
+    let mut pos = 0;
+
+    for wrapped in wrap(&plain, options) {
+        // Wrapped lines are the paragraph's words in order, minus the
+        // whitespace where it was broken
+        let start = pos + plain[pos..].find(wrapped.as_ref()).unwrap_or(0);
+        let end = start + wrapped.len();
+        pos = end;
+
+        let mut spans = Vec::new();
+        let mut offset = 0;
+        for (text, style) in segments {
+            let (from, to) = (start.max(offset), end.min(offset + text.len()));
+            if from < to {
+                spans.push(Span::styled(plain[from..to].to_string(), *style));
+            }
+            offset += text.len();
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+/// Pad lines to the width of the widest so they line up when centered.
+fn pad_block(lines: &mut [Line]) {
+    let width = lines.iter().map(Line::width).max().unwrap_or(0);
+    for line in lines {
+        let padding = width - line.width();
+        if padding > 0 {
+            line.spans.push(Span::raw(" ".repeat(padding)));
+        }
+    }
+}
+
+/// Card text with media references shown as `[image: name]` and
+/// `[sound: name]` placeholders, and whether there were any.
+fn media_placeholders(content: &str) -> (String, bool) {
+    let refs = media_refs(content);
+    let mut text = String::with_capacity(content.len());
+    let mut pos = 0;
+    for media in &refs {
+        let kind = match media.kind {
+            MediaKind::Image => "image",
+            MediaKind::Sound => "sound",
+        };
+        text.push_str(&content[pos..media.range.start]);
+        text.push_str(&format!("[{}: {}]", kind, media.name));
+        pos = media.range.end;
+    }
+    text.push_str(&content[pos..]);
+    (text, !refs.is_empty())
+}
+
 // ══════════════════════════════════════════════════════════════════════════
 // Rating Buttons Widget
 // ══════════════════════════════════════════════════════════════════════════